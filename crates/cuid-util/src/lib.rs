@@ -1,4 +1,15 @@
 //! Common utility functions for CUID generation
+//!
+//! This crate is `no_std`, and requires only `alloc`.
+
+#![no_std]
+
+extern crate alloc;
+
+#[cfg(test)]
+extern crate std;
+
+use alloc::string::{String, ToString};
 
 // =============================================================================
 // UTILITY FUNCTIONS
@@ -64,6 +75,134 @@ pub fn to_base_36<N: Into<u128>>(number: N) -> String {
     buffer
 }
 
+/// The maximum number of base36 digits needed to represent a u128.
+pub const MAX_BASE_36_LEN: usize = 25;
+
+/// Base36 digits, indexed by value.
+pub const BASE_36_DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// Converts any number representable as a u128 into base36, without
+/// allocating.
+///
+/// The digits are written to the end of the provided buffer, and the written
+/// portion is returned.
+///
+/// ```
+/// use cuid_util::{to_base_36_in, MAX_BASE_36_LEN};
+///
+/// let mut buf = [0; MAX_BASE_36_LEN];
+/// assert_eq!("2s", to_base_36_in(100_u8, &mut buf));
+/// ```
+pub fn to_base_36_in<N: Into<u128>>(number: N, buffer: &mut [u8; MAX_BASE_36_LEN]) -> &str {
+    const RADIX: u128 = 36;
+    let mut number = number.into();
+    let mut start = MAX_BASE_36_LEN;
+
+    // Write at least one digit, so that zero is "0"
+    loop {
+        start -= 1;
+        buffer[start] = BASE_36_DIGITS[(number % RADIX) as usize];
+        number /= RADIX;
+        if number == 0 {
+            break;
+        }
+    }
+
+    // SAFETY: we have written only ASCII digits to this portion of the buffer
+    unsafe { core::str::from_utf8_unchecked(&buffer[start..]) }
+}
+
+// Converting Digests to Base36
+// =============================
+
+/// The maximum number of base36 digits needed to represent a 512-bit digest.
+pub const MAX_DIGEST_BASE_36_LEN: usize = 100;
+
+/// Buffer size for [`digest_to_base_36_in()`].
+///
+/// Digits are produced six at a time, so this is `MAX_DIGEST_BASE_36_LEN`
+/// rounded up to the next multiple of six.
+pub const DIGEST_BASE_36_BUF_LEN: usize = MAX_DIGEST_BASE_36_LEN.next_multiple_of(6);
+
+/// Converts a 512-bit (64 byte) big-endian digest to base36, without
+/// allocating, returning at most `max_len` of its leading digits.
+///
+/// The output is the same as that of
+/// `BigUint::from_bytes_be(digest).to_str_radix(36)` truncated to `max_len`
+/// characters, but is much faster, since the digest's fixed width allows
+/// the conversion to use a fixed array of machine-word limbs.
+///
+/// ```
+/// use cuid_util::{digest_to_base_36_in, DIGEST_BASE_36_BUF_LEN};
+///
+/// let mut digest = [0; 64];
+/// digest[62] = 1;
+///
+/// let mut buf = [0; DIGEST_BASE_36_BUF_LEN];
+/// assert_eq!("74", digest_to_base_36_in(&digest, usize::MAX, &mut buf));
+/// assert_eq!("7", digest_to_base_36_in(&digest, 1, &mut buf));
+/// ```
+pub fn digest_to_base_36_in<'b>(
+    digest: &[u8; 64],
+    max_len: usize,
+    buffer: &'b mut [u8; DIGEST_BASE_36_BUF_LEN],
+) -> &'b str {
+    // 36^6 is the largest power of 36 that fits in a u32, so each pass of long
+    // division over the 32-bit limbs yields six digits.
+    const CHUNK_DIGITS: usize = 6;
+    const CHUNK_DIVISOR: u64 = 36_u64.pow(CHUNK_DIGITS as u32);
+
+    // Big-endian limbs, i.e. the most significant limb comes first
+    let mut limbs = [0_u32; 16];
+    // 64 bytes split evenly into 4 byte chunks, so there is no remainder
+    let (chunks, _) = digest.as_chunks::<4>();
+    limbs
+        .iter_mut()
+        .zip(chunks)
+        .for_each(|(limb, bytes)| *limb = u32::from_be_bytes(*bytes));
+
+    // Index of the most significant non-zero limb
+    let mut first = limbs
+        .iter()
+        .position(|limb| *limb != 0)
+        .unwrap_or(limbs.len());
+    let mut start = DIGEST_BASE_36_BUF_LEN;
+
+    while first < limbs.len() {
+        let mut remainder = 0;
+        for limb in &mut limbs[first..] {
+            let acc = (remainder << 32) | u64::from(*limb);
+            // The quotient always fits in a u32, because remainder < divisor
+            *limb = (acc / CHUNK_DIVISOR) as u32;
+            remainder = acc % CHUNK_DIVISOR;
+        }
+        while first < limbs.len() && limbs[first] == 0 {
+            first += 1;
+        }
+
+        for _ in 0..CHUNK_DIGITS {
+            start -= 1;
+            buffer[start] = BASE_36_DIGITS[(remainder % 36) as usize];
+            remainder /= 36;
+        }
+    }
+
+    // The last chunk may have been padded with leading zeros. Strip them, but
+    // leave a single zero if the digest was zero.
+    while start < DIGEST_BASE_36_BUF_LEN - 1 && buffer[start] == b'0' {
+        start += 1;
+    }
+    if start == DIGEST_BASE_36_BUF_LEN {
+        start -= 1;
+        buffer[start] = b'0';
+    }
+
+    let end = start + (DIGEST_BASE_36_BUF_LEN - start).min(max_len);
+
+    // SAFETY: we have written only ASCII digits to this portion of the buffer
+    unsafe { core::str::from_utf8_unchecked(&buffer[start..end]) }
+}
+
 /// Trait for types that can be converted to base 36.
 pub trait ToBase36 {
     fn to_base_36(self) -> String;
@@ -81,6 +220,8 @@ where
 
 #[cfg(test)]
 mod tests {
+    use std::format;
+
     use super::*;
 
     use proptest::prelude::*;
@@ -103,5 +244,38 @@ mod tests {
                 &val
             )
         }
+
+        #[test]
+        fn in_buffer_matches_allocating(n: u128) {
+            let mut buf = [0; MAX_BASE_36_LEN];
+            assert_eq!(to_base_36(n), to_base_36_in(n, &mut buf));
+        }
+
+        #[test]
+        fn digest_matches_biguint(digest: [u8; 64], max_len in 0..=MAX_DIGEST_BASE_36_LEN) {
+            let expected = num::bigint::BigUint::from_bytes_be(&digest).to_str_radix(36);
+            let mut buf = [0; DIGEST_BASE_36_BUF_LEN];
+            assert_eq!(&expected, digest_to_base_36_in(&digest, usize::MAX, &mut buf));
+            assert_eq!(
+                &expected[..expected.len().min(max_len)],
+                digest_to_base_36_in(&digest, max_len, &mut buf)
+            );
+        }
+    }
+
+    #[test]
+    fn digest_edges() {
+        let mut buf = [0; DIGEST_BASE_36_BUF_LEN];
+        assert_eq!("0", digest_to_base_36_in(&[0; 64], usize::MAX, &mut buf));
+
+        let mut one = [0; 64];
+        one[63] = 1;
+        assert_eq!("1", digest_to_base_36_in(&one, usize::MAX, &mut buf));
+
+        let max = [u8::MAX; 64];
+        let expected = num::bigint::BigUint::from_bytes_be(&max).to_str_radix(36);
+        assert_eq!(MAX_DIGEST_BASE_36_LEN, expected.len());
+        assert_eq!(&expected, digest_to_base_36_in(&max, usize::MAX, &mut buf));
+        assert_eq!(&expected[..32], digest_to_base_36_in(&max, 32, &mut buf));
     }
 }
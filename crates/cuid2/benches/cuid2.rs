@@ -1,5 +1,7 @@
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use cuid2::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
 
 fn bench_create_id(c: &mut Criterion) {
     c.bench_function("generate cuid2", |b| b.iter(create_id));
@@ -15,6 +17,13 @@ fn bench_create_many_ids(c: &mut Criterion) {
     });
 }
 
+fn bench_create_ids_batch(c: &mut Criterion) {
+    let constructor = CuidConstructor::new();
+    c.bench_function("generate many cuid2 in a batch", |b| {
+        b.iter(|| constructor.create_ids(10_000))
+    });
+}
+
 fn bench_create_small_id(c: &mut Criterion) {
     let constructor = CuidConstructor::new().with_length(10);
     c.bench_function("generate small cuid2", |b| {
@@ -22,11 +31,122 @@ fn bench_create_small_id(c: &mut Criterion) {
     });
 }
 
+const TIMESTAMP: u64 = 1_700_000_000_000;
+const FINGERPRINT: &str = "gqz1cxk6bn4h1k5ezxlw2xlt9k9m4d0n";
+
+/// Creates a CUID with the per-character entropy sampling that bulk random
+/// bytes replaced, hashing it with the same other inputs as a constructor.
+fn create_id_with_gen_range<R: Rng>(rng: &mut R, counter: u64, length: u16) -> String {
+    let mut entropy = [0; CuidConstructor::MAX_LENGTH as usize];
+    let entropy = &mut entropy[..usize::from(length)];
+    for byte in entropy.iter_mut() {
+        *byte = cuid_util::BASE_36_DIGITS[rng.gen_range(0..36)];
+    }
+    let entropy = std::str::from_utf8(entropy).unwrap();
+    let first_letter = char::from(b'a' + rng.gen_range(0..26));
+    create_id_from_parts(
+        TIMESTAMP,
+        entropy,
+        counter,
+        FINGERPRINT,
+        first_letter,
+        length,
+    )
+    .unwrap()
+}
+
+fn bench_entropy(c: &mut Criterion) {
+    let mut group = c.benchmark_group("entropy");
+    for length in [10, 24, 32] {
+        let constructor =
+            CuidConstructor::from_parts(ChaCha20Rng::seed_from_u64(1), FixedClock::new(TIMESTAMP))
+                .with_length(length);
+        group.bench_function(BenchmarkId::new("bulk bytes", length), |b| {
+            b.iter(|| constructor.create_id())
+        });
+
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        let mut counter = 0;
+        group.bench_function(BenchmarkId::new("gen_range", length), |b| {
+            b.iter(|| {
+                counter += 1;
+                create_id_with_gen_range(&mut rng, counter, length)
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_create_id_into(c: &mut Criterion) {
+    let constructor = CuidConstructor::new();
+    let mut buf = [0; 24];
+    c.bench_function("generate cuid2 into buffer", |b| {
+        b.iter(|| {
+            constructor.create_id_into(&mut buf);
+        })
+    });
+}
+
+fn bench_write_id(c: &mut Criterion) {
+    let constructor = CuidConstructor::new();
+    let mut out = String::with_capacity(24);
+    c.bench_function("write cuid2", |b| {
+        b.iter(|| {
+            out.clear();
+            constructor.write_id(&mut out).unwrap();
+        })
+    });
+}
+
+fn bench_create_inline_id(c: &mut Criterion) {
+    let constructor = CuidConstructor::new();
+    c.bench_function("generate inline cuid2", |b| {
+        b.iter(|| constructor.create_inline_id())
+    });
+}
+
+/// The per-char check that `is_cuid2()` made before it used a lookup table,
+/// kept as a baseline.
+fn is_cuid2_scalar(id: &str) -> bool {
+    let length = CuidConstructor::MIN_LENGTH.into()..=CuidConstructor::MAX_LENGTH.into();
+    let mut chars = id.chars();
+    length.contains(&id.len())
+        && chars.next().is_some_and(|ch| ch.is_ascii_lowercase())
+        && chars.all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit())
+}
+
+fn bench_validate_many_ids(c: &mut Criterion) {
+    let mut ids = CuidConstructor::new().create_ids(10_000);
+    // Invalidate every tenth ID, so that not every check succeeds
+    for id in ids.iter_mut().step_by(10) {
+        id.replace_range(12..13, "-");
+    }
+    let validator = Validator::new();
+    c.bench_function("validate many cuid2 with a validator", |b| {
+        b.iter(|| ids.iter().filter(|id| !validator.is_valid(id)).count())
+    });
+    c.bench_function("validate many cuid2 per char", |b| {
+        b.iter(|| ids.iter().filter(|id| !is_cuid2_scalar(id)).count())
+    });
+    c.bench_function("validate many cuid2 with is_cuid2", |b| {
+        b.iter(|| ids.iter().filter(|id| !is_cuid2(id)).count())
+    });
+    c.bench_function("validate many cuid2 with validate_many", |b| {
+        b.iter(|| validate_many(&ids))
+    });
+}
+
 criterion_group!(
     cuid2,
     bench_create_id,
     bench_create_many_ids,
-    bench_create_small_id
+    bench_create_ids_batch,
+    bench_create_small_id,
+    bench_entropy,
+    bench_create_id_into,
+    bench_write_id,
+    bench_create_inline_id,
+    bench_validate_many_ids
 );
 
 criterion_main!(cuid2);
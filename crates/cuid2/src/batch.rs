@@ -0,0 +1,222 @@
+//! Creating many CUIDs at once
+
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+use core::iter::FusedIterator;
+#[cfg(feature = "std")]
+use std::io;
+
+use sha3::{Digest, Sha3_512};
+
+use crate::{expect_id, CuidConstructor, MAX_ID_LEN};
+
+impl CuidConstructor {
+    /// Creates `n` new CUIDs.
+    ///
+    /// Unlike calling [`create_id()`](Self::create_id) in a loop, the RNG,
+    /// the default counter and fingerprint, and the hasher are fetched once
+    /// and reused for the whole batch. With a custom RNG, its lock is held
+    /// until the batch is complete, rather than being taken for every CUID.
+    /// Likewise, the thread's counter and fingerprint are borrowed, and
+    /// checked for a `fork()`, once per batch. The exception is a constructor
+    /// with a custom clock, counter, or fingerprinter, which may itself create
+    /// CUIDs, so the thread's state is then accessed separately for each CUID.
+    ///
+    /// Like [`create_id()`](Self::create_id), this panics if a custom clock or
+    /// fingerprinter returns an error.
+    ///
+    /// ```
+    /// use cuid2::CuidConstructor;
+    ///
+    /// let ids = CuidConstructor::new().create_ids(100);
+    ///
+    /// assert_eq!(100, ids.len());
+    /// assert!(ids.iter().all(cuid2::is_cuid2));
+    /// ```
+    pub fn create_ids(&self, n: usize) -> Vec<String> {
+        let mut hasher = Sha3_512::new();
+        let mut buf = [0; MAX_ID_LEN];
+        self.with_sources(|rng, defaults| {
+            (0..n)
+                .map(|_| {
+                    expect_id(self.write_with(&mut hasher, rng, defaults, &mut buf)).to_owned()
+                })
+                .collect()
+        })
+    }
+
+    /// Writes `n` new CUIDs to `writer`, separated by `separator`.
+    ///
+    /// Like [`create_ids()`](Self::create_ids), this reuses the RNG, the
+    /// default counter and fingerprint, and the hasher for the whole batch.
+    /// No separator is written after the last CUID. Consider wrapping the
+    /// writer in a [`BufWriter`](std::io::BufWriter).
+    ///
+    /// ```
+    /// use cuid2::CuidConstructor;
+    ///
+    /// let mut out = Vec::new();
+    /// CuidConstructor::new().write_ids(3, &mut out, "\n").unwrap();
+    ///
+    /// let out = String::from_utf8(out).unwrap();
+    /// assert_eq!(3, out.lines().filter(|id| cuid2::is_cuid2(id)).count());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn write_ids<W: io::Write + ?Sized>(
+        &self,
+        n: usize,
+        writer: &mut W,
+        separator: &str,
+    ) -> io::Result<()> {
+        let mut hasher = Sha3_512::new();
+        let mut buf = [0; MAX_ID_LEN];
+        self.with_sources(|rng, defaults| {
+            for i in 0..n {
+                if i > 0 {
+                    writer.write_all(separator.as_bytes())?;
+                }
+                let id = expect_id(self.write_with(&mut hasher, rng, defaults, &mut buf));
+                writer.write_all(id.as_bytes())?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Returns an infinite iterator of new CUIDs.
+    ///
+    /// The hasher is reused for every CUID, but the RNG and the default
+    /// counter and fingerprint are fetched for each one, as by
+    /// [`create_id()`](Self::create_id), so that the iterator may be held
+    /// across other uses of the constructor (or a `fork()`). To create a known
+    /// number of CUIDs, prefer [`create_ids()`](Self::create_ids), which
+    /// fetches them once for the whole batch.
+    ///
+    /// ```
+    /// use cuid2::CuidConstructor;
+    ///
+    /// let constructor = CuidConstructor::new();
+    /// let ids: Vec<_> = constructor.iter().take(10).collect();
+    ///
+    /// assert_eq!(10, ids.len());
+    /// ```
+    pub fn iter(&self) -> CuidIter<'_> {
+        CuidIter {
+            constructor: self,
+            hasher: Sha3_512::new(),
+        }
+    }
+}
+
+/// An infinite iterator of new CUIDs.
+///
+/// Created by [`CuidConstructor::iter()`].
+#[derive(Clone, Debug)]
+pub struct CuidIter<'c> {
+    constructor: &'c CuidConstructor,
+    hasher: Sha3_512,
+}
+
+impl Iterator for CuidIter<'_> {
+    type Item = String;
+
+    #[inline]
+    fn next(&mut self) -> Option<String> {
+        let mut buf = [0; MAX_ID_LEN];
+        let id = self
+            .constructor
+            .write_with_hasher(&mut self.hasher, &mut buf);
+        Some(expect_id(id).to_owned())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+impl FusedIterator for CuidIter<'_> {}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use std::collections::HashSet;
+
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use super::*;
+    use crate::{is_cuid2, Clock, ClockError, FixedClock, GeneratorScope, SystemClock};
+
+    #[test]
+    fn batches_are_unique_and_valid() {
+        let constructors = [
+            CuidConstructor::new().with_length(10),
+            CuidConstructor::new().with_scope(GeneratorScope::Process),
+            CuidConstructor::new().with_rng(ChaCha20Rng::seed_from_u64(1)),
+        ];
+
+        for constructor in constructors {
+            let ids = constructor.create_ids(1_000);
+            assert_eq!(1_000, ids.iter().collect::<HashSet<_>>().len());
+            assert!(ids.iter().all(is_cuid2));
+
+            let mut out = Vec::new();
+            constructor.write_ids(1_000, &mut out, ",").unwrap();
+            let out = String::from_utf8(out).unwrap();
+            assert_eq!(1_000, out.split(',').filter(|id| is_cuid2(id)).count());
+        }
+    }
+
+    #[test]
+    fn batch_matches_individual_ids() {
+        let constructor = || {
+            CuidConstructor::new()
+                .with_rng(ChaCha20Rng::seed_from_u64(1))
+                .with_clock(FixedClock::new(1_700_000_000_000))
+        };
+
+        let individual = constructor();
+        let expected: Vec<_> = (0..10).map(|_| individual.create_id()).collect();
+        assert_eq!(expected, constructor().create_ids(10));
+        assert_eq!(expected, constructor().iter().take(10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn batch_borrows_thread_state_once() {
+        let start = crate::scope::with_thread_state(|state| state.next_count());
+        CuidConstructor::new().create_ids(10);
+        let next = crate::scope::with_thread_state(|state| state.next_count());
+        assert_eq!(start + 11, next);
+
+        // A custom counter may create CUIDs on the same thread mid-batch
+        let nested = CuidConstructor::new().with_counter(|| {
+            assert!(is_cuid2(crate::create_id()));
+            0
+        });
+        assert_eq!(10, nested.create_ids(10).len());
+    }
+
+    #[test]
+    fn batch_allows_reentrant_clock() {
+        struct Reentrant;
+
+        impl Clock for Reentrant {
+            fn now_millis(&self) -> Result<u64, ClockError> {
+                assert!(is_cuid2(crate::create_id()));
+                SystemClock.now_millis()
+            }
+        }
+
+        let constructor = CuidConstructor::new().with_clock(Reentrant);
+        assert!(is_cuid2(constructor.create_id()));
+        assert_eq!(10, constructor.create_ids(10).len());
+
+        let mut out = Vec::new();
+        constructor.write_ids(10, &mut out, ",").unwrap();
+        assert_eq!(10, out.split(|&b| b == b',').count());
+    }
+
+    #[test]
+    fn write_ids_without_ids_writes_nothing() {
+        let mut out = Vec::new();
+        CuidConstructor::new().write_ids(0, &mut out, ",").unwrap();
+        assert!(out.is_empty());
+    }
+}
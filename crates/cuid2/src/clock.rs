@@ -0,0 +1,246 @@
+//! Time sources for CUID construction
+
+use alloc::{borrow::Cow, sync::Arc};
+use core::fmt;
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "std")]
+use std::{
+    error::Error,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// A source of timestamps for CUID construction.
+///
+/// With the `std` feature, the default is the `SystemClock`. Alternatives may
+/// be configured with
+/// [`CuidConstructor::with_clock()`](crate::CuidConstructor::with_clock).
+pub trait Clock: Send + Sync {
+    /// Returns the current time as milliseconds since the Unix epoch.
+    fn now_millis(&self) -> Result<u64, ClockError>;
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now_millis(&self) -> Result<u64, ClockError> {
+        (**self).now_millis()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for &'static C {
+    fn now_millis(&self) -> Result<u64, ClockError> {
+        (**self).now_millis()
+    }
+}
+
+/// The error returned when a [`Clock`] cannot provide the current time.
+#[derive(Clone, Debug)]
+pub struct ClockError {
+    reason: Cow<'static, str>,
+}
+
+impl ClockError {
+    /// Creates a new error with the given reason.
+    pub fn new<S: Into<Cow<'static, str>>>(reason: S) -> Self {
+        Self {
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for ClockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to read clock: {}", self.reason)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for ClockError {}
+
+/// The system's wall clock.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now_millis(&self) -> Result<u64, ClockError> {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            // Use timestamp as milliseconds to match JS implementation
+            .map(|time| time.as_millis())
+            // `.duration_since()` fails if the end time is not later than the
+            // start time, so this will only fail if the system time is before
+            // 1970-01-01.
+            .map_err(|_| ClockError::new("current system time is set to before the Unix epoch"))?;
+
+        u64::try_from(millis)
+            .map_err(|_| ClockError::new("current system time is too far in the future"))
+    }
+}
+
+/// A clock that always returns the same time.
+///
+/// This is mostly useful for testing.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedClock {
+    millis: u64,
+}
+
+impl FixedClock {
+    /// Creates a clock fixed at the given number of milliseconds since the
+    /// Unix epoch.
+    pub const fn new(millis: u64) -> Self {
+        Self { millis }
+    }
+}
+
+impl Clock for FixedClock {
+    fn now_millis(&self) -> Result<u64, ClockError> {
+        Ok(self.millis)
+    }
+}
+
+/// A clock that only changes when told to.
+///
+/// This is mostly useful for testing. Wrap it in an `Arc` to keep a handle
+/// to it after passing it to a constructor:
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use cuid2::{Clock, CuidConstructor, ManualClock};
+///
+/// let clock = Arc::new(ManualClock::new(0));
+/// let constructor = CuidConstructor::new().with_clock(Arc::clone(&clock));
+///
+/// clock.advance(1_000);
+/// assert_eq!(1_000, clock.now_millis().unwrap());
+/// ```
+#[cfg(target_has_atomic = "64")]
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    millis: AtomicU64,
+}
+
+#[cfg(target_has_atomic = "64")]
+impl ManualClock {
+    /// Creates a clock set to the given number of milliseconds since the Unix
+    /// epoch.
+    pub const fn new(millis: u64) -> Self {
+        Self {
+            millis: AtomicU64::new(millis),
+        }
+    }
+
+    /// Sets the clock to the given number of milliseconds since the Unix
+    /// epoch.
+    pub fn set(&self, millis: u64) {
+        self.millis.store(millis, Ordering::Relaxed);
+    }
+
+    /// Moves the clock forward by the given number of milliseconds.
+    pub fn advance(&self, millis: u64) {
+        self.millis.fetch_add(millis, Ordering::Relaxed);
+    }
+}
+
+#[cfg(target_has_atomic = "64")]
+impl Clock for ManualClock {
+    fn now_millis(&self) -> Result<u64, ClockError> {
+        Ok(self.millis.load(Ordering::Relaxed))
+    }
+}
+
+/// A clock that never goes backwards, even if the system clock does.
+///
+/// The clock reads the system time once, when it is created, and afterwards
+/// advances with the monotonic [`Instant`] clock. It is therefore unaffected
+/// by adjustments to the system clock, such as those made by NTP. Call
+/// [`resync()`](Self::resync) to correct for any drift from the system clock;
+/// the returned time will still never decrease.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MonotonicClock {
+    anchor: AtomicU64,
+    started: Instant,
+    last: AtomicU64,
+}
+
+#[cfg(feature = "std")]
+impl MonotonicClock {
+    /// Creates a clock starting from the current system time.
+    pub fn new() -> Result<Self, ClockError> {
+        let now = SystemClock.now_millis()?;
+        Ok(Self {
+            anchor: AtomicU64::new(now),
+            started: Instant::now(),
+            last: AtomicU64::new(now),
+        })
+    }
+
+    /// Re-anchors the clock to the current system time.
+    ///
+    /// If the system time is behind this clock, the clock will hold its
+    /// current time until the system time catches up.
+    pub fn resync(&self) -> Result<(), ClockError> {
+        let now = SystemClock.now_millis()?;
+        self.anchor
+            .store(now.saturating_sub(self.elapsed_millis()), Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn elapsed_millis(&self) -> u64 {
+        u64::try_from(self.started.elapsed().as_millis()).unwrap_or(u64::MAX)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for MonotonicClock {
+    fn now_millis(&self) -> Result<u64, ClockError> {
+        let now = self
+            .anchor
+            .load(Ordering::Relaxed)
+            .saturating_add(self.elapsed_millis());
+        let last = self.last.fetch_max(now, Ordering::Relaxed);
+        Ok(now.max(last))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn system_clock() {
+        let expected = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let actual = SystemClock.now_millis().unwrap();
+        assert!(actual >= expected && actual - expected < 1_000);
+    }
+
+    #[test]
+    fn manual_clock() {
+        let clock = ManualClock::new(5);
+        clock.advance(10);
+        assert_eq!(15, clock.now_millis().unwrap());
+        clock.set(1);
+        assert_eq!(1, clock.now_millis().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn monotonic_clock_never_decreases() {
+        let clock = MonotonicClock::new().unwrap();
+        let start = clock.now_millis().unwrap();
+
+        // Simulate the system clock having jumped backwards
+        clock.anchor.store(0, Ordering::Relaxed);
+        assert_eq!(start, clock.now_millis().unwrap());
+
+        clock.resync().unwrap();
+        assert!(clock.now_millis().unwrap() >= start);
+    }
+}
@@ -0,0 +1,38 @@
+//! Counters for CUID construction
+
+/// A source of counter values for CUID construction.
+///
+/// By default, each thread has its own randomly initialized counter.
+/// Alternatives may be configured with
+/// [`CuidConstructor::with_counter()`](crate::CuidConstructor::with_counter).
+///
+/// This is implemented for any `Fn() -> u64` closure or function, so shared
+/// state like an `Arc<AtomicU64>` can be captured:
+///
+/// ```
+/// use std::sync::{
+///     atomic::{AtomicU64, Ordering},
+///     Arc,
+/// };
+///
+/// use cuid2::CuidConstructor;
+///
+/// let count = Arc::new(AtomicU64::new(0));
+/// let constructor = CuidConstructor::new().with_counter({
+///     let count = Arc::clone(&count);
+///     move || count.fetch_add(1, Ordering::Relaxed)
+/// });
+///
+/// constructor.create_id();
+/// assert_eq!(1, count.load(Ordering::Relaxed));
+/// ```
+pub trait Counter: Send + Sync {
+    /// Returns the next counter value.
+    fn next_count(&self) -> u64;
+}
+
+impl<F: Fn() -> u64 + Send + Sync> Counter for F {
+    fn next_count(&self) -> u64 {
+        self()
+    }
+}
@@ -0,0 +1,99 @@
+//! Diesel support for [`Cuid2`], enabled with the `diesel` feature.
+//!
+//! A [`Cuid2`] maps to the `Text` SQL type, and can be used with any backend
+//! that supports `String` for `Text` columns, including SQLite and Postgres.
+//! Loading a value that is not a valid CUID fails with a deserialization
+//! error wrapping a [`ParseCuid2Error`](crate::ParseCuid2Error).
+
+use ::diesel::{
+    backend::Backend,
+    deserialize::{self, FromSql},
+    serialize::{self, Output, ToSql},
+    sql_types::Text,
+};
+
+use crate::Cuid2;
+
+impl<DB: Backend> ToSql<Text, DB> for Cuid2
+where
+    str: ToSql<Text, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        self.as_str().to_sql(out)
+    }
+}
+
+impl<DB: Backend> FromSql<Text, DB> for Cuid2
+where
+    String: FromSql<Text, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        Ok(Cuid2::try_from(String::from_sql(bytes)?)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::diesel::{
+        connection::SimpleConnection, expression::AsExpression, pg::Pg, prelude::*,
+        sql_types::Nullable, sqlite::SqliteConnection,
+    };
+
+    use super::*;
+
+    ::diesel::table! {
+        ids (id) {
+            id -> Text,
+        }
+    }
+
+    fn connect() -> SqliteConnection {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.batch_execute("CREATE TABLE ids (id TEXT PRIMARY KEY NOT NULL)")
+            .unwrap();
+        conn
+    }
+
+    #[test]
+    fn sqlite_roundtrip() {
+        let mut conn = connect();
+        let id = crate::create_cuid2();
+
+        ::diesel::insert_into(ids::table)
+            .values(ids::id.eq(&id))
+            .execute(&mut conn)
+            .unwrap();
+
+        let fetched: Cuid2 = ids::table
+            .select(ids::id)
+            .filter(ids::id.eq(&id))
+            .get_result(&mut conn)
+            .unwrap();
+        assert_eq!(id, fetched);
+    }
+
+    #[test]
+    fn sqlite_rejects_invalid() {
+        let mut conn = connect();
+        conn.batch_execute("INSERT INTO ids (id) VALUES ('Not-A-Cuid')")
+            .unwrap();
+
+        let res = ids::table.select(ids::id).get_result::<Cuid2>(&mut conn);
+        assert!(
+            matches!(res, Err(::diesel::result::Error::DeserializationError(_))),
+            "{res:?}"
+        );
+    }
+
+    #[test]
+    fn postgres_text() {
+        fn assert_sql_type<T>()
+        where
+            T: ToSql<Text, Pg> + FromSql<Text, Pg> + AsExpression<Text>,
+            T: AsExpression<Nullable<Text>>,
+        {
+        }
+
+        assert_sql_type::<Cuid2>();
+    }
+}
@@ -0,0 +1,76 @@
+//! Errors from CUID construction
+
+use core::fmt;
+
+use crate::{prefixed::INVALID_PREFIX, ClockError, CuidConstructor, FingerprintError};
+
+/// The error returned when a CUID cannot be created.
+///
+/// ```
+/// use cuid2::{CuidConstructor, Error};
+///
+/// assert!(matches!(
+///     CuidConstructor::new().try_with_length(1),
+///     Err(Error::InvalidLength { length: 1 })
+/// ));
+/// ```
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The requested length is outside of
+    /// [`MIN_LENGTH`](CuidConstructor::MIN_LENGTH) to
+    /// [`MAX_LENGTH`](CuidConstructor::MAX_LENGTH).
+    InvalidLength {
+        /// The requested length.
+        length: u16,
+    },
+    /// The clock could not provide the current time.
+    Clock(ClockError),
+    /// The fingerprinter could not provide a fingerprint.
+    Fingerprint(FingerprintError),
+    /// The prefix for a [`PrefixedConstructor`](crate::PrefixedConstructor)
+    /// is invalid.
+    InvalidPrefix,
+    /// The first letter given to
+    /// [`create_id_from_parts()`](crate::create_id_from_parts) is not a
+    /// lowercase ASCII letter.
+    InvalidFirstLetter {
+        /// The given letter.
+        letter: char,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength { length } => write!(
+                f,
+                "Invalid CUID length {length}: must be from {} to {}",
+                CuidConstructor::MIN_LENGTH,
+                CuidConstructor::MAX_LENGTH
+            ),
+            Self::Clock(err) => err.fmt(f),
+            Self::Fingerprint(err) => err.fmt(f),
+            Self::InvalidPrefix => f.write_str(INVALID_PREFIX),
+            Self::InvalidFirstLetter { letter } => write!(
+                f,
+                "Invalid first letter {letter:?}: must be a lowercase ASCII letter"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+impl From<ClockError> for Error {
+    fn from(err: ClockError) -> Self {
+        Self::Clock(err)
+    }
+}
+
+impl From<FingerprintError> for Error {
+    fn from(err: FingerprintError) -> Self {
+        Self::Fingerprint(err)
+    }
+}
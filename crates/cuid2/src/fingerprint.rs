@@ -0,0 +1,69 @@
+//! Fingerprints for CUID construction
+
+use alloc::{borrow::Cow, string::String};
+use core::fmt;
+
+/// A source of fingerprints for CUID construction.
+///
+/// A fingerprint distinguishes CUIDs created by different hosts, processes,
+/// or threads. By default, each thread has its own fingerprint, derived from
+/// random data, the process ID, and the thread ID. Alternatives may be
+/// configured with
+/// [`CuidConstructor::with_fingerprinter()`](crate::CuidConstructor::with_fingerprinter).
+///
+/// This is implemented for any `Fn() -> String` closure or function. To avoid
+/// allocating for every CUID, or to report failures, implement it directly:
+///
+/// ```
+/// use std::borrow::Cow;
+///
+/// use cuid2::{CuidConstructor, Fingerprint, FingerprintError};
+///
+/// struct Host {
+///     name: String,
+/// }
+///
+/// impl Fingerprint for Host {
+///     fn fingerprint(&self) -> Result<Cow<'_, str>, FingerprintError> {
+///         Ok(Cow::Borrowed(&self.name))
+///     }
+/// }
+///
+/// let host = Host { name: "web-1".into() };
+/// let constructor = CuidConstructor::new().with_fingerprinter(host);
+/// assert_eq!(24, constructor.create_id().len());
+/// ```
+pub trait Fingerprint: Send + Sync {
+    /// Returns the fingerprint to include in the next CUID.
+    fn fingerprint(&self) -> Result<Cow<'_, str>, FingerprintError>;
+}
+
+impl<F: Fn() -> String + Send + Sync> Fingerprint for F {
+    fn fingerprint(&self) -> Result<Cow<'_, str>, FingerprintError> {
+        Ok(Cow::Owned(self()))
+    }
+}
+
+/// The error returned when a [`Fingerprint`] cannot provide a fingerprint.
+#[derive(Clone, Debug)]
+pub struct FingerprintError {
+    reason: Cow<'static, str>,
+}
+
+impl FingerprintError {
+    /// Creates a new error with the given reason.
+    pub fn new<S: Into<Cow<'static, str>>>(reason: S) -> Self {
+        Self {
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for FingerprintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to create fingerprint: {}", self.reason)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FingerprintError {}
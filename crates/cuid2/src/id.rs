@@ -0,0 +1,642 @@
+//! Validated CUID2 types, owned and borrowed
+
+use alloc::{
+    borrow::{Borrow, ToOwned},
+    string::String,
+};
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    str::FromStr,
+};
+
+use crate::{is_cuid2, CuidConstructor, CUID_BYTES, FIRST_BYTE, LATER_BYTE, MAX_ID_LEN};
+
+/// An owned, validated CUID2.
+///
+/// A `Cuid2` can only be obtained from a [`CuidConstructor`](crate::CuidConstructor)
+/// (see [`CuidConstructor::create_cuid2()`](crate::CuidConstructor::create_cuid2))
+/// or by parsing a string that
+/// passes [`is_cuid2()`], so holding one is proof that the contained string is
+/// a well-formed CUID.
+///
+/// Comparison, ordering, and hashing all behave exactly as they do for the
+/// underlying string, so a `Cuid2` may be looked up in a map by `&str`.
+///
+/// ```
+/// use cuid2::Cuid2;
+///
+/// let id: Cuid2 = "tz4a98xxat96iws9zmbrgj3a".parse().unwrap();
+/// assert_eq!("tz4a98xxat96iws9zmbrgj3a", id.as_str());
+///
+/// assert!("not a cuid".parse::<Cuid2>().is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "diesel",
+    derive(diesel::expression::AsExpression, diesel::deserialize::FromSqlRow),
+    diesel(sql_type = diesel::sql_types::Text)
+)]
+pub struct Cuid2(String);
+
+impl Cuid2 {
+    /// Wraps a string without validating it.
+    ///
+    /// Only for use with strings produced by a `CuidConstructor`.
+    pub(crate) fn from_string_unchecked(id: String) -> Self {
+        Self(id)
+    }
+
+    /// Returns a reference to the underlying `String`.
+    #[cfg(feature = "sqlx")]
+    pub(crate) fn as_string(&self) -> &String {
+        &self.0
+    }
+
+    /// Returns the ID as a string slice.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Consumes the ID, returning the underlying `String`.
+    #[inline]
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    /// Returns the ID as a borrowed [`Cuid2Str`].
+    #[inline]
+    pub fn as_cuid2_str(&self) -> &Cuid2Str {
+        Cuid2Str::from_str_unchecked(&self.0)
+    }
+}
+
+impl fmt::Display for Cuid2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Deref for Cuid2 {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Cuid2 {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Cuid2 {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for Cuid2 {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Cuid2 {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl From<Cuid2> for String {
+    fn from(id: Cuid2) -> Self {
+        id.0
+    }
+}
+
+impl FromStr for Cuid2 {
+    type Err = ParseCuid2Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if is_cuid2(s) {
+            Ok(Self(s.to_owned()))
+        } else {
+            Err(ParseCuid2Error(()))
+        }
+    }
+}
+
+impl TryFrom<&str> for Cuid2 {
+    type Error = ParseCuid2Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl TryFrom<String> for Cuid2 {
+    type Error = ParseCuid2Error;
+
+    /// Validates the string, reusing its allocation on success.
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if is_cuid2(&value) {
+            Ok(Self(value))
+        } else {
+            Err(ParseCuid2Error(()))
+        }
+    }
+}
+
+impl Borrow<Cuid2Str> for Cuid2 {
+    fn borrow(&self) -> &Cuid2Str {
+        self.as_cuid2_str()
+    }
+}
+
+impl AsRef<Cuid2Str> for Cuid2 {
+    fn as_ref(&self) -> &Cuid2Str {
+        self.as_cuid2_str()
+    }
+}
+
+impl PartialEq<Cuid2Str> for Cuid2 {
+    fn eq(&self, other: &Cuid2Str) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl PartialEq<&Cuid2Str> for Cuid2 {
+    fn eq(&self, other: &&Cuid2Str) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl From<&Cuid2Str> for Cuid2 {
+    fn from(id: &Cuid2Str) -> Self {
+        id.to_owned()
+    }
+}
+
+impl From<InlineCuid2> for Cuid2 {
+    fn from(id: InlineCuid2) -> Self {
+        Self(id.as_str().to_owned())
+    }
+}
+
+/// A borrowed, validated CUID2: the borrowed counterpart of [`Cuid2`], as
+/// `str` is to `String`.
+///
+/// A `&Cuid2Str` can validate an ID in place, such as inside a larger buffer,
+/// without allocating. Comparison, ordering, and hashing all behave exactly
+/// as they do for the underlying string and for [`Cuid2`], so a map keyed by
+/// `Cuid2` may be looked up by `&Cuid2Str`.
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use cuid2::{Cuid2, Cuid2Str};
+///
+/// let line = "user tz4a98xxat96iws9zmbrgj3a logged in";
+/// let id = Cuid2Str::new(&line[5..29]).unwrap();
+/// assert_eq!("tz4a98xxat96iws9zmbrgj3a", id.as_str());
+///
+/// let mut logins: HashMap<Cuid2, u32> = HashMap::new();
+/// logins.insert(id.to_owned(), 1);
+/// assert_eq!(Some(&1), logins.get(id));
+///
+/// assert!(Cuid2Str::new(&line[..10]).is_err());
+/// ```
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Cuid2Str(str);
+
+impl Cuid2Str {
+    /// Validates a string slice as a CUID, without copying it.
+    pub fn new(id: &str) -> Result<&Self, ParseCuid2Error> {
+        if is_cuid2(id) {
+            Ok(Self::from_str_unchecked(id))
+        } else {
+            Err(ParseCuid2Error(()))
+        }
+    }
+
+    /// Wraps a string slice without validating it.
+    ///
+    /// Only for use with strings already known to be valid CUIDs.
+    pub(crate) fn from_str_unchecked(id: &str) -> &Self {
+        // SAFETY: Cuid2Str is a repr(transparent) wrapper around str, so the
+        // pointer casts between them preserve the layout and metadata.
+        unsafe { &*(id as *const str as *const Self) }
+    }
+
+    /// Returns the ID as a string slice.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Validates a CUID literal for the [`cuid2!`](crate::cuid2!) macro.
+    ///
+    /// When evaluated in a const context, an invalid literal fails
+    /// compilation with a message pointing at the invalid character.
+    #[doc(hidden)]
+    pub const fn from_literal(id: &'static str) -> &'static Self {
+        let bytes = id.as_bytes();
+        if bytes.len() < CuidConstructor::MIN_LENGTH as usize || bytes.len() > MAX_ID_LEN {
+            panic!("invalid CUID literal: must be from 2 to 76 characters");
+        }
+        if CUID_BYTES[bytes[0] as usize] & FIRST_BYTE == 0 {
+            literal_error(id, 0, "expected a lowercase ASCII letter");
+        }
+        let mut i = 1;
+        while i < bytes.len() {
+            if CUID_BYTES[bytes[i] as usize] & LATER_BYTE == 0 {
+                literal_error(id, i, "expected a lowercase ASCII letter or digit");
+            }
+            i += 1;
+        }
+        // SAFETY: Cuid2Str is a repr(transparent) wrapper around str, so the
+        // pointer casts between them preserve the layout and metadata.
+        unsafe { &*(id as *const str as *const Self) }
+    }
+}
+
+/// Fails compilation of a [`cuid2!`](crate::cuid2!) literal, quoting it with
+/// a caret under the invalid byte at `index`.
+///
+/// `id` is at most `MAX_ID_LEN` bytes, and every byte before `index` is ASCII.
+const fn literal_error(id: &str, index: usize, expected: &str) -> ! {
+    // Room for the message and the quoted literal, with the caret line
+    // indented as far as the literal's last byte
+    const BUF_LEN: usize = 128 + 2 * MAX_ID_LEN;
+
+    const fn push(buf: &mut [u8; BUF_LEN], mut len: usize, bytes: &[u8]) -> usize {
+        let mut i = 0;
+        while i < bytes.len() {
+            buf[len] = bytes[i];
+            len += 1;
+            i += 1;
+        }
+        len
+    }
+
+    let mut buf = [b' '; BUF_LEN];
+    let mut len = push(&mut buf, 0, b"invalid CUID literal\n  \"");
+    len = push(&mut buf, len, id.as_bytes());
+    len = push(&mut buf, len, b"\"\n");
+    // Indent past the leading space and quote, then to the invalid byte
+    len += 3 + index;
+    len = push(&mut buf, len, b"^ ");
+    len = push(&mut buf, len, expected.as_bytes());
+
+    match core::str::from_utf8(buf.split_at(len).0) {
+        Ok(message) => panic!("{}", message),
+        // Unreachable: the message is built from whole strings and spaces
+        Err(_) => panic!("invalid CUID literal"),
+    }
+}
+
+impl fmt::Display for Cuid2Str {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Deref for Cuid2Str {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Cuid2Str {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<Cuid2Str> for Cuid2Str {
+    fn as_ref(&self) -> &Cuid2Str {
+        self
+    }
+}
+
+impl Borrow<str> for Cuid2Str {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl ToOwned for Cuid2Str {
+    type Owned = Cuid2;
+
+    fn to_owned(&self) -> Cuid2 {
+        Cuid2(self.0.to_owned())
+    }
+}
+
+impl PartialEq<str> for Cuid2Str {
+    fn eq(&self, other: &str) -> bool {
+        &self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Cuid2Str {
+    fn eq(&self, other: &&str) -> bool {
+        &self.0 == *other
+    }
+}
+
+impl PartialEq<Cuid2> for Cuid2Str {
+    fn eq(&self, other: &Cuid2) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl PartialEq<Cuid2> for &Cuid2Str {
+    fn eq(&self, other: &Cuid2) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl<'a> TryFrom<&'a str> for &'a Cuid2Str {
+    type Error = ParseCuid2Error;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        Cuid2Str::new(value)
+    }
+}
+
+impl<'a> From<&'a Cuid2Str> for &'a str {
+    fn from(id: &'a Cuid2Str) -> Self {
+        id.as_str()
+    }
+}
+
+/// Creates a `&'static` [`Cuid2Str`] from a string literal, validated at
+/// compile time.
+///
+/// This is intended for hardcoded IDs in tests and fixtures, so that a
+/// mistyped ID fails to compile rather than failing at runtime.
+///
+/// ```
+/// use cuid2::{cuid2, Cuid2, Cuid2Str};
+///
+/// const ADMIN: &Cuid2Str = cuid2!("tz4a98xxat96iws9zmbrgj3a");
+///
+/// let owned: Cuid2 = ADMIN.to_owned();
+/// assert_eq!("tz4a98xxat96iws9zmbrgj3a", owned.as_str());
+/// ```
+///
+/// An invalid literal fails to compile, with an error like:
+///
+/// ```text
+/// invalid CUID literal
+///   "tz4A98xxat96iws9zmbrgj3a"
+///       ^ expected a lowercase ASCII letter or digit
+/// ```
+///
+/// ```compile_fail
+/// let id = cuid2::cuid2!("tz4A98xxat96iws9zmbrgj3a");
+/// ```
+///
+/// ```compile_fail
+/// let id = cuid2::cuid2!("1z4a98xxat96iws9zmbrgj3a");
+/// ```
+///
+/// ```compile_fail
+/// let id = cuid2::cuid2!("a");
+/// ```
+#[macro_export]
+macro_rules! cuid2 {
+    ($id:literal) => {{
+        const ID: &$crate::Cuid2Str = $crate::Cuid2Str::from_literal($id);
+        ID
+    }};
+}
+
+/// A CUID stored inline, without any heap allocation.
+///
+/// An `InlineCuid2` is created with
+/// [`CuidConstructor::create_inline_id()`](crate::CuidConstructor::create_inline_id),
+/// and can hold CUIDs of up to [`InlineCuid2::CAPACITY`] characters. Unlike
+/// [`Cuid2`], it is `Copy`.
+///
+/// Comparison, ordering, and hashing all behave exactly as they do for the
+/// underlying string.
+#[derive(Clone, Copy)]
+pub struct InlineCuid2 {
+    bytes: [u8; InlineCuid2::CAPACITY],
+    len: u8,
+}
+
+impl InlineCuid2 {
+    /// The maximum length of an inline CUID.
+    pub const CAPACITY: usize = 32;
+
+    /// Wraps the first `len` bytes of a buffer containing a generated CUID.
+    pub(crate) fn from_buf(bytes: [u8; Self::CAPACITY], len: usize) -> Self {
+        debug_assert!(len <= Self::CAPACITY);
+        Self {
+            bytes,
+            len: len as u8,
+        }
+    }
+
+    /// Returns the ID as a string slice.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        // SAFETY: the bytes are only ever written by a CuidConstructor, which
+        // writes only ASCII letters and digits.
+        unsafe { core::str::from_utf8_unchecked(&self.bytes[..usize::from(self.len)]) }
+    }
+}
+
+impl fmt::Display for InlineCuid2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for InlineCuid2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("InlineCuid2").field(&self.as_str()).finish()
+    }
+}
+
+impl Deref for InlineCuid2 {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for InlineCuid2 {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for InlineCuid2 {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq for InlineCuid2 {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for InlineCuid2 {}
+
+impl PartialEq<str> for InlineCuid2 {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for InlineCuid2 {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialOrd for InlineCuid2 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for InlineCuid2 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for InlineCuid2 {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+/// The error returned when a string is not a valid CUID2.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseCuid2Error(pub(crate) ());
+
+impl fmt::Display for ParseCuid2Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid CUID2: expected a lowercase ASCII letter followed by 1 to 75 lowercase ASCII letters or digits")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseCuid2Error {}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "std")]
+    use std::collections::HashMap;
+
+    use super::*;
+    #[cfg(feature = "std")]
+    use crate::{create_cuid2, CuidConstructor};
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn created_ids_are_valid() {
+        let id = create_cuid2();
+        assert_eq!(24, id.len());
+        assert_eq!(id, id.as_str().parse::<Cuid2>().unwrap());
+
+        let id = CuidConstructor::new().with_length(10).create_cuid2();
+        assert_eq!(10, id.len());
+    }
+
+    #[test]
+    fn parse_rejects_invalid() {
+        assert!(Cuid2::try_from("").is_err());
+        assert!(Cuid2::try_from("1abc").is_err());
+        assert!(Cuid2::try_from(String::from("Abc")).is_err());
+        assert!("ab#".parse::<Cuid2>().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn behaves_like_str() {
+        let id = create_cuid2();
+        let key = id.to_string();
+
+        let mut map = HashMap::new();
+        map.insert(id.clone(), 1);
+        assert_eq!(Some(&1), map.get(key.as_str()));
+
+        let other = create_cuid2();
+        assert_eq!(id.cmp(&other), id.as_str().cmp(other.as_str()));
+        assert_eq!(key, String::from(id));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn borrowed_behaves_like_owned() {
+        let id = create_cuid2();
+        let borrowed = Cuid2Str::new(id.as_str()).unwrap();
+        assert_eq!(id, borrowed);
+        assert_eq!(borrowed, id);
+        assert_eq!(id, borrowed.to_owned());
+
+        let mut map = HashMap::new();
+        map.insert(id.clone(), 1);
+        assert_eq!(Some(&1), map.get(borrowed));
+        assert_eq!(Some(&1), map.get(id.as_cuid2_str()));
+
+        let other = create_cuid2();
+        let other_borrowed = other.as_cuid2_str();
+        assert_eq!(id.cmp(&other), borrowed.cmp(other_borrowed));
+        assert_eq!(id.to_string(), borrowed.to_string());
+    }
+
+    #[test]
+    fn borrowed_rejects_invalid() {
+        assert!(Cuid2Str::new("").is_err());
+        assert!(Cuid2Str::new("1abc").is_err());
+        assert!(<&Cuid2Str>::try_from("ab#").is_err());
+        assert!(<&Cuid2Str>::try_from("ab").is_ok());
+    }
+
+    #[test]
+    fn literals_are_validated() {
+        const ID: &Cuid2Str = crate::cuid2!("tz4a98xxat96iws9zmbrgj3a");
+        assert_eq!("tz4a98xxat96iws9zmbrgj3a", ID.as_str());
+        assert_eq!(Cuid2Str::new("ab"), Ok(crate::cuid2!("ab")));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid CUID literal\n  \"abC\"\n     ^ expected")]
+    fn literal_error_points_at_char() {
+        // Outside a const context, an invalid literal panics at runtime
+        let _ = Cuid2Str::from_literal("abC");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn inline_behaves_like_str() {
+        let id = CuidConstructor::new().create_inline_id();
+        let key = id.to_string();
+
+        let mut map = HashMap::new();
+        map.insert(id, 1);
+        assert_eq!(Some(&1), map.get(key.as_str()));
+
+        let other = CuidConstructor::new().create_inline_id();
+        assert_eq!(id.cmp(&other), id.as_str().cmp(other.as_str()));
+        assert_eq!(Cuid2::from(id), key.as_str());
+    }
+}
@@ -46,6 +46,84 @@
 //! assert_eq!(32, id.len());
 //! ```
 //!
+//! If you would like the type system to distinguish CUIDs from arbitrary
+//! strings, use [`create_cuid2()`] or [`CuidConstructor::create_cuid2()`] to
+//! get a validated [`Cuid2`], or parse one from a string:
+//!
+//! ```
+//! use cuid2::Cuid2;
+//!
+//! let id = cuid2::create_cuid2();
+//! assert_eq!(24, id.len());
+//!
+//! let parsed: Cuid2 = id.as_str().parse().unwrap();
+//! assert_eq!(id, parsed);
+//! ```
+//!
+//! Hardcoded IDs, such as in tests and fixtures, can be validated at compile
+//! time with the [`cuid2!`] macro, which creates a borrowed `&Cuid2Str`:
+//!
+//! ```
+//! use cuid2::{cuid2, Cuid2Str};
+//!
+//! const ADMIN: &Cuid2Str = cuid2!("tz4a98xxat96iws9zmbrgj3a");
+//! assert_eq!("tz4a98xxat96iws9zmbrgj3a", ADMIN.as_str());
+//! ```
+//!
+//! To tag CUIDs with their entity type, like `user_tz4a98xxat96iws9zmbrgj3a`,
+//! use a [`PrefixedConstructor`]:
+//!
+//! ```
+//! use cuid2::CuidConstructor;
+//!
+//! let users = CuidConstructor::new().with_prefix("user").unwrap();
+//! let id = users.create_id();
+//!
+//! assert!(cuid2::is_prefixed_cuid2(&id, "user"));
+//! assert_eq!("user", users.parse(&id).unwrap().prefix());
+//! ```
+//!
+//! CUID creation panics if a custom clock or fingerprinter fails. To handle
+//! these failures, and an invalid length, use the fallible variants, which
+//! return an [`Error`]:
+//!
+//! ```
+//! use cuid2::CuidConstructor;
+//!
+//! let constructor = CuidConstructor::new().try_with_length(16)?;
+//! let id = constructor.try_create_id()?;
+//!
+//! assert_eq!(16, id.len());
+//! # Ok::<(), cuid2::Error>(())
+//! ```
+//!
+//! ## Features
+//!
+//! - `serde`: `Serialize` and `Deserialize` for [`Cuid2`], validating on
+//!   deserialization. See the `serde` module for a compact binary encoding.
+//! - `sqlx`: `Type`, `Encode`, and `Decode` for [`Cuid2`] as text, for any
+//!   sqlx database that supports `String` (including SQLite and Postgres).
+//! - `diesel`: `ToSql`, `FromSql`, and `AsExpression` for [`Cuid2`] as `Text`,
+//!   for any Diesel backend that supports `String` (including SQLite and
+//!   Postgres).
+//! - `rayon`: [`par_create_ids()`] and [`CuidConstructor::par_ids()`], for
+//!   creating CUIDs in parallel on rayon's thread pool.
+//! - `std` (default): thread-local counters and fingerprints, the
+//!   [`SystemClock`], and the free functions like [`create_id()`] which rely
+//!   on them.
+//! - `testing`: the `testing` module, with histogram, chi-square, and
+//!   collision checks of CUID quality for any [`CuidConstructor`].
+//! - `uuid`: [`Cuid2::to_uuid()`] and [`Cuid2::from_uuid()`], with `TryFrom`
+//!   impls, packing CUIDs of up to [`MAX_PACKED_LEN`] characters into UUIDs.
+//!
+//! ## `no_std`
+//!
+//! With default features disabled, this crate is `no_std` and requires only
+//! `alloc`. Since there is no thread-local state, system clock, or system RNG,
+//! the RNG and clock must be supplied with [`CuidConstructor::from_parts()`].
+//! Unless a custom counter and fingerprinter are also configured, the counter
+//! and fingerprint are derived from the RNG.
+//!
 //! If installed with `cargo install`, this package also provides a `cuid2`
 //! binary, which generates a CUID on the command line. It can be used like:
 //!
@@ -54,73 +132,118 @@
 //! y3cfw1hafbtezzflns334sb2
 //! ```
 
-use std::{
-    cell::RefCell,
-    collections::hash_map::DefaultHasher,
-    hash::{Hash, Hasher},
-    time::{SystemTime, UNIX_EPOCH},
-};
+#![cfg_attr(not(feature = "std"), no_std)]
 
-use cuid_util::ToBase36;
-use num::bigint;
-use rand::{seq::SliceRandom, thread_rng, Rng};
+extern crate alloc;
+
+mod batch;
+mod clock;
+mod counter;
+#[cfg(feature = "diesel")]
+mod diesel;
+mod error;
+mod fingerprint;
+mod id;
+mod packed;
+mod prefixed;
+mod random;
+#[cfg(feature = "rayon")]
+mod rayon;
+#[cfg(feature = "std")]
+mod scope;
+#[cfg(feature = "serde")]
+pub mod serde;
+#[cfg(feature = "sqlx")]
+mod sqlx;
+mod sync;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod validator;
+
+use alloc::{borrow::ToOwned, string::String, sync::Arc, vec::Vec};
+use core::fmt;
+
+use cuid_util::{
+    digest_to_base_36_in, to_base_36_in, BASE_36_DIGITS, DIGEST_BASE_36_BUF_LEN, MAX_BASE_36_LEN,
+};
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::{seq::SliceRandom, CryptoRng, Rng, RngCore};
+use random::{CustomRng, DefaultState, RngSource};
 use sha3::{Digest, Sha3_512};
 
+pub use batch::CuidIter;
+#[cfg(target_has_atomic = "64")]
+pub use clock::ManualClock;
+pub use clock::{Clock, ClockError, FixedClock};
+#[cfg(feature = "std")]
+pub use clock::{MonotonicClock, SystemClock};
+pub use counter::Counter;
+pub use error::Error;
+pub use fingerprint::{Fingerprint, FingerprintError};
+pub use id::{Cuid2, Cuid2Str, InlineCuid2, ParseCuid2Error};
+pub use packed::{PackCuid2Error, MAX_PACKED_LEN};
+pub use prefixed::{
+    is_prefixed_cuid2, ParsePrefixedCuid2Error, PrefixedConstructor, PrefixedCuid2,
+};
+#[cfg(feature = "rayon")]
+pub use rayon::par_create_ids;
+#[cfg(feature = "std")]
+use scope::ProcessState;
+#[cfg(feature = "std")]
+pub use scope::{reseed_after_fork, GeneratorScope};
+pub use validator::{ValidationError, Validator};
+
 // =============================================================================
 // CONSTANTS
 // =============================================================================
 
 const DEFAULT_LENGTH: u8 = 24;
 const BIG_LENGTH: u8 = 32;
+/// Upper bound (exclusive) for randomly initialized counter values.
+// Updated 2023-08-08 to match updated reference implementation, which notes:
+// > ~22k hosts before 50% chance of initial counter collision
+// > with a remaining counter range of 9.0e+15 in JavaScript.
+const COUNTER_INIT_MAX: u64 = 476_782_367;
 // valid characters to start an ID
 const STARTING_CHARS: &str = "abcdefghijklmnopqrstuvwxyz";
 
-// =============================================================================
-// THREAD LOCALS
-// =============================================================================
-// Each thread generating CUIDs gets its own:
-// - 64-bit counter, randomly initialized to some value between 0 and 2056, inclusive
-// - fingerprint, a hash with added entropy, derived from a random number between
-//   2063 and 4125, inclusive, the process ID, and the thread ID
-
-thread_local! {
-    /// Value used to initialize the counter. After the counter hits u64::MAX, it
-    /// will roll back to this value.
-    // Updated 2023-08-08 to match updated reference implementation, which notes:
-    // > ~22k hosts before 50% chance of initial counter collision
-    // > with a remaining counter range of 9.0e+15 in JavaScript.
-    static COUNTER_INIT: u64 = thread_rng().gen_range(0..476_782_367);
-
-    /// Use an individual counter per thread, starting at a randomly initialized value.
-    ///
-    /// Range of randomly initialized values taken from reference implementation.
-    static COUNTER: RefCell<u64> = COUNTER_INIT.with(|val| RefCell::new(*val));
-
-    /// Fingerprint! The original implementation is a hash of:
-    /// - stringified keys of the global object
-    /// - added entropy
-    ///
-    /// For us, we'll use
-    /// - A few random numbers
-    /// - the process ID
-    /// - the thread ID (which also ensures our CUIDs will be different per thread)
-    ///
-    /// This is pretty non-language, non-system dependent, so it allows us to
-    /// compile to wasm and so on.
-    static FINGERPRINT: String = hash(
-        [
-            thread_rng().gen::<u128>().to_be_bytes(),
-            thread_rng().gen::<u128>().to_be_bytes(),
-            u128::from(std::process::id()).to_be_bytes(),
-            u128::from(get_thread_id()).to_be_bytes(),
-        ],
-        BIG_LENGTH.into(),
-    );
-}
+/// Set in [`CUID_BYTES`] for bytes that may start a CUID.
+const FIRST_BYTE: u8 = 0b01;
+/// Set in [`CUID_BYTES`] for bytes that may follow the first byte of a CUID.
+const LATER_BYTE: u8 = 0b10;
+
+/// Classifies every byte for validation: lowercase ASCII letters may appear
+/// anywhere in a CUID, and digits anywhere but the start.
+const CUID_BYTES: [u8; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < STARTING_CHARS.len() {
+        table[STARTING_CHARS.as_bytes()[i] as usize] = FIRST_BYTE | LATER_BYTE;
+        i += 1;
+    }
+    let mut digit = b'0';
+    while digit <= b'9' {
+        table[digit as usize] = LATER_BYTE;
+        digit += 1;
+    }
+    table
+};
 
 // Hashing
 // =======
 
+/// The number of base36 digits that a 512-bit hash reliably has.
+///
+/// A hash has up to 100 digits, but only about 12% of hashes have that many,
+/// and about 2% have fewer than 99. Every hash of at least 2^384, i.e. all but
+/// a 2^-128 fraction of them, has at least 75.
+const RELIABLE_DIGEST_BASE_36_LEN: usize = 75;
+
+/// The maximum length of a generated ID: a starting char plus the digits
+/// that a 512-bit hash reliably has.
+const MAX_ID_LEN: usize = RELIABLE_DIGEST_BASE_36_LEN + 1;
+
 /// Hash a value, including an additional salt of randomly generated data.
 //
 // Updated 2023-08-08 to match the updated JS implementation, which is:
@@ -142,22 +265,8 @@ fn hash<S: AsRef<[u8]>, T: IntoIterator<Item = S>>(input: T, length: u16) -> Str
         hasher.update(block.as_ref());
     }
 
-    // 512 bits (64 bytes) of data ([u8; 64])
-    let hash = hasher.finalize();
-
-    // We'll convert the bytes directly to a big, unsigned int and then use
-    // its builtin radix conversion.
-    //
-    // We don't use bigint for the rest of our base conversions, because it's
-    // significantly slower, but we use it here since we need to deal with the
-    // 512-bit integer from the hash function.
-    let mut res = bigint::BigUint::from_bytes_be(&hash).to_str_radix(36);
-
-    // Note that truncate panics if the length does not fall on a char boundary,
-    // but we don't need to worry about that since all the chars will be ASCII.
-    res.truncate(length.into());
-
-    res
+    let mut buf = [0; DIGEST_BASE_36_BUF_LEN];
+    digest_to_base_36_in(&hasher.finalize().into(), length.into(), &mut buf).to_owned()
 }
 
 // Other Utility Functions
@@ -179,19 +288,51 @@ fn hash<S: AsRef<[u8]>, T: IntoIterator<Item = S>>(input: T, length: u16) -> Str
 /// ```
 #[inline]
 pub fn is_cuid2<S: AsRef<str>>(to_check: S) -> bool {
-    let to_check = to_check.as_ref();
-    const MAX_LENGTH: usize = BIG_LENGTH as usize;
-    match to_check.len() {
-        2..=MAX_LENGTH => {
-            STARTING_CHARS.contains(&to_check[..1])
-                && to_check[1..].chars().fold(true, |acc, ch| {
-                    acc && (ch.is_ascii_lowercase()) || ch.is_ascii_digit()
-                })
+    is_cuid2_bytes(to_check.as_ref().as_bytes())
+}
+
+/// Return whether a byte string is a legitimate CUID2, without first checking
+/// that it is UTF-8.
+///
+/// This is equivalent to [`is_cuid2()`], since a CUID is only ever ASCII.
+/// ```rust
+/// assert!(cuid2::is_cuid2_bytes(b"tz4a98xxat96iws9zmbrgj3a"));
+/// assert!(!cuid2::is_cuid2_bytes(b"tz4a98xxat96iws9zmbrgj3\xff"));
+/// ```
+#[inline]
+pub fn is_cuid2_bytes(to_check: &[u8]) -> bool {
+    const MIN_LENGTH: usize = CuidConstructor::MIN_LENGTH as usize;
+    match to_check {
+        [first, rest @ ..] if (MIN_LENGTH..=MAX_ID_LEN).contains(&to_check.len()) => {
+            // Combine the whole tail without branching, which is faster than
+            // exiting early for IDs this short and lets the loop vectorize.
+            CUID_BYTES[usize::from(*first)] & FIRST_BYTE != 0
+                && rest
+                    .iter()
+                    .fold(LATER_BYTE, |acc, byte| acc & CUID_BYTES[usize::from(*byte)])
+                    != 0
         }
         _ => false,
     }
 }
 
+/// Validates many CUIDs at once, returning the indices of the invalid ones in
+/// ascending order.
+///
+/// Each ID is checked with [`is_cuid2_bytes()`], so this accepts `&str`,
+/// `String`, and raw bytes alike.
+/// ```rust
+/// let ids = ["tz4a98xxat96iws9zmbrgj3a", "not a cuid", "pfh0haxfpzowht3oi213cqos", ""];
+/// assert_eq!(vec![1, 3], cuid2::validate_many(&ids));
+/// ```
+pub fn validate_many<S: AsRef<[u8]>>(ids: &[S]) -> Vec<usize> {
+    ids.iter()
+        .enumerate()
+        .filter(|(_, id)| !is_cuid2_bytes(id.as_ref()))
+        .map(|(index, _)| index)
+        .collect()
+}
+
 /// Return whether a string is a legitimate CUID.
 ///
 /// This is an alias of [is_cuid2]
@@ -200,80 +341,66 @@ pub fn is_cuid<S: AsRef<str>>(to_check: S) -> bool {
     is_cuid2(to_check)
 }
 
-/// Creates a random string of the specified length.
-fn create_entropy(length: u16) -> String {
-    let mut rng = thread_rng();
-    let length: usize = length.into();
-
-    // Allocate a string with the appropriate capacity to avoid reallocation.
-    //
-    // The string is generated and then pushed to until its desired length is
-    // reached or exceeded. We therefore allocate enough for the length plus
-    // the maximum value it might be exceeded by. The values pushed to the
-    // string are random numbers from 0 to 36, converted to base 36.
-    // Therefore, the maximum overfill is 36 in base 36, i.e. 10, which is 2
-    // chars
-    let mut result = String::with_capacity(length + 2);
-
-    while result.len() < length {
-        // Matches reference implementation logic as of 2023-08-08, which is:
-        // ```js
-        // entropy = entropy + Math.floor(random() * 36).toString(36);
-        // ```
-        let random_val = rng.gen_range(0u128..36u128);
-        result.push_str(&random_val.to_base_36());
-    }
-
-    result
-}
+/// The number of random bytes that map evenly onto base36 digits: the largest
+/// multiple of 36 that fits in a byte.
+const ENTROPY_BYTE_LIMIT: u8 = 252;
 
-/// Retrieves the current timestmap and converts to Base36.
-fn get_timestamp() -> String {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        // Use timestamp as milliseconds to match JS implementation
-        .map(|time| time.as_millis().to_base_36())
-        // Panic safety: `.duration_since()` fails if the end time is not
-        // later than the start time, so this will only fail if the system
-        // time is before 1970-01-01. It is impossible on Unix systems to set
-        // a time before then, since the entire system uses a 32 or 64 bit
-        // unsigned integer for time, where zero is midnight 1970-01-01.
-        //
-        // If you are on a system that for some reason both can be and needs to
-        // be set >50 years in the past AND this library not working is a
-        // problem for you, please feel free to reach out.
-        .expect(
-            "Failed to calculate system timestamp! Current system time may be \
-                 set to before the Unix epoch, or time may otherwise be broken. \
-                 Cannot continue",
-        )
-}
+/// Base36 digits for random bytes below `ENTROPY_BYTE_LIMIT`.
+///
+/// Each digit appears exactly seven times, so a uniformly random byte below
+/// the limit yields a uniformly random digit.
+const ENTROPY_DIGITS: [u8; ENTROPY_BYTE_LIMIT as usize] = {
+    let mut digits = [0; ENTROPY_BYTE_LIMIT as usize];
+    let mut i = 0;
+    while i < digits.len() {
+        digits[i] = BASE_36_DIGITS[i % 36];
+        i += 1;
+    }
+    digits
+};
 
-/// Retrieves and increments the counter value.
-fn get_count() -> u64 {
-    COUNTER.with(|cell| {
-        cell.replace_with(|counter| {
-            counter
-                .checked_add(1)
-                // if we hit u64::MAX, roll back to the original thread-local
-                // initialization value
-                .unwrap_or_else(|| COUNTER_INIT.with(|x| *x))
-        })
-    })
+/// Fills `buf` with random base36 digits.
+//
+// Matches the distribution of the reference implementation logic as of
+// 2023-08-08, which is:
+// ```js
+// entropy = entropy + Math.floor(random() * 36).toString(36);
+// ```
+//
+// Rather than sampling a number for each digit, random bytes are generated in
+// bulk and mapped through a lookup table. Bytes at or above the limit would
+// bias the result towards the first few digits, so they are rejected and
+// replaced with more random bytes.
+fn fill_entropy<R: Rng + ?Sized>(buf: &mut [u8], rng: &mut R) {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let start = filled;
+        rng.fill_bytes(&mut buf[start..]);
+        // Compact the accepted digits in place. `filled` never passes `i`, so
+        // no random byte is overwritten before it is read.
+        for i in start..buf.len() {
+            let byte = buf[i];
+            if byte < ENTROPY_BYTE_LIMIT {
+                buf[filled] = ENTROPY_DIGITS[usize::from(byte)];
+                filled += 1;
+            }
+        }
+    }
 }
 
-/// Retrieves the thread-local fingerprint.
-fn get_fingerprint() -> String {
-    FINGERPRINT.with(|x| x.clone())
-}
+/// Hashes a random string of the specified length, without allocating.
+fn hash_entropy<R: Rng + ?Sized>(hasher: &mut Sha3_512, length: u16, rng: &mut R) {
+    // Since the hasher consumes its input incrementally, we can generate the
+    // entropy in fixed-size chunks rather than allocating the whole thing.
+    let mut chunk = [0; 32];
+    let mut remaining = usize::from(length);
 
-/// Retrieves the current thread's ID.
-fn get_thread_id() -> u64 {
-    // ThreadId doesn't implement debug or display, but it does implement Hash,
-    // so we can get the hash value to use in our fingerprint.
-    let mut hasher = DefaultHasher::new();
-    std::thread::current().id().hash(&mut hasher);
-    hasher.finish()
+    while remaining > 0 {
+        let len = remaining.min(chunk.len());
+        fill_entropy(&mut chunk[..len], rng);
+        hasher.update(&chunk[..len]);
+        remaining -= len;
+    }
 }
 
 // =============================================================================
@@ -293,90 +420,566 @@ fn get_thread_id() -> u64 {
 ///
 /// assert_eq!(32, CuidConstructor::new().with_length(32).create_id().len());
 /// ```
+///
+/// Constructors are `Send`, `Sync`, and cheap to clone, so a configured
+/// constructor may be kept in shared application state. Clones share any
+/// custom counter, fingerprinter, RNG, and clock, including the counter and
+/// fingerprint derived from a custom RNG.
+#[derive(Clone, Debug)]
 pub struct CuidConstructor {
     length: u16,
-    counter: fn() -> u64,
-    fingerprinter: fn() -> String,
+    counter: CounterSource,
+    fingerprinter: FingerprintSource,
+    rng: RngSource,
+    clock: ClockSource,
+    #[cfg(feature = "std")]
+    scope: GeneratorScope,
 }
 impl CuidConstructor {
+    /// The minimum length of a CUID: a starting character and one character
+    /// of hash.
+    pub const MIN_LENGTH: u16 = 2;
+
+    /// The maximum length of a CUID: a starting character and as many base36
+    /// digits as the 512-bit hash reliably has.
+    pub const MAX_LENGTH: u16 = MAX_ID_LEN as u16;
+
     /// Creates a new constructor with default settings.
+    #[cfg(feature = "std")]
     pub const fn new() -> Self {
         Self {
             length: DEFAULT_LENGTH as u16,
-            counter: get_count,
-            fingerprinter: get_fingerprint,
+            counter: CounterSource::Default,
+            fingerprinter: FingerprintSource::Default,
+            rng: RngSource::Thread,
+            clock: ClockSource::System,
+            scope: GeneratorScope::Thread,
+        }
+    }
+
+    /// Creates a new constructor that uses the specified random number
+    /// generator and clock, and otherwise has default settings.
+    ///
+    /// This is the only way to create a constructor without `std`. See
+    /// [`with_rng()`](Self::with_rng) and [`with_clock()`](Self::with_clock)
+    /// for details.
+    ///
+    /// ```
+    /// use cuid2::{CuidConstructor, FixedClock};
+    /// use rand::{rngs::StdRng, SeedableRng};
+    ///
+    /// let constructor = CuidConstructor::from_parts(StdRng::from_entropy(), FixedClock::new(0));
+    /// assert_eq!(24, constructor.create_id().len());
+    /// ```
+    pub fn from_parts<R, C>(rng: R, clock: C) -> Self
+    where
+        R: RngCore + CryptoRng + Send + 'static,
+        C: Clock + 'static,
+    {
+        Self {
+            length: DEFAULT_LENGTH as u16,
+            counter: CounterSource::Default,
+            fingerprinter: FingerprintSource::Default,
+            rng: RngSource::Custom(Arc::new(CustomRng::new(rng))),
+            clock: ClockSource::Custom(Arc::new(clock)),
+            #[cfg(feature = "std")]
+            scope: GeneratorScope::Thread,
         }
     }
 
     /// Returns a new constructor that will generate CUIDs with the specified length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length is less than [`MIN_LENGTH`](Self::MIN_LENGTH) or
+    /// greater than [`MAX_LENGTH`](Self::MAX_LENGTH). See
+    /// [`try_with_length()`](Self::try_with_length) for a fallible version.
     pub fn with_length(self, length: u16) -> Self {
-        Self { length, ..self }
+        self.try_with_length(length)
+            .unwrap_or_else(|err| panic!("{err}"))
     }
 
-    /// Returns a new constructor with the specified counter function.
-    pub fn with_counter(self, counter: fn() -> u64) -> Self {
-        Self { counter, ..self }
+    /// Returns a new constructor that will generate CUIDs with the specified
+    /// length, or an error if the length is less than
+    /// [`MIN_LENGTH`](Self::MIN_LENGTH) or greater than
+    /// [`MAX_LENGTH`](Self::MAX_LENGTH).
+    ///
+    /// ```
+    /// use cuid2::CuidConstructor;
+    ///
+    /// assert!(CuidConstructor::new().try_with_length(16).is_ok());
+    /// assert!(CuidConstructor::new().try_with_length(0).is_err());
+    /// assert!(CuidConstructor::new().try_with_length(77).is_err());
+    /// ```
+    pub fn try_with_length(self, length: u16) -> Result<Self, Error> {
+        check_length(length)?;
+        Ok(Self { length, ..self })
+    }
+
+    /// Returns a new constructor with the specified counter, which may be a
+    /// function or closure returning a `u64`.
+    ///
+    /// See [`Counter`] for an example.
+    pub fn with_counter<C: Counter + 'static>(self, counter: C) -> Self {
+        Self {
+            counter: CounterSource::Custom(Arc::new(counter)),
+            ..self
+        }
     }
 
-    /// Returns a new constructor with the specified fingerprinter function.
-    pub fn with_fingerprinter(self, fingerprinter: fn() -> String) -> Self {
+    /// Returns a new constructor with the specified fingerprinter, which may
+    /// be a function or closure returning a `String`.
+    ///
+    /// See [`Fingerprint`] for an example.
+    pub fn with_fingerprinter<F: Fingerprint + 'static>(self, fingerprinter: F) -> Self {
         Self {
-            fingerprinter,
+            fingerprinter: FingerprintSource::Custom(Arc::new(fingerprinter)),
             ..self
         }
     }
 
+    /// Returns a new constructor that uses the specified random number
+    /// generator, such as `OsRng` or a seeded `ChaCha20Rng`.
+    ///
+    /// The RNG provides the entropy and starting character for each CUID.
+    /// Unless a custom counter or fingerprinter is also specified, it is
+    /// also used to initialize a counter and to create a fingerprint, which
+    /// are shared by all users of the constructor rather than being
+    /// thread-local. This mirrors `init({ random })` in the reference
+    /// implementation.
+    ///
+    /// The RNG is shared behind a lock, so generating CUIDs concurrently from
+    /// many threads with a single constructor will be slower than with the
+    /// default, thread-local RNG.
+    ///
+    /// ```
+    /// use cuid2::CuidConstructor;
+    /// use rand::rngs::OsRng;
+    ///
+    /// let constructor = CuidConstructor::new().with_rng(OsRng);
+    /// assert_eq!(24, constructor.create_id().len());
+    /// ```
+    pub fn with_rng<R: RngCore + CryptoRng + Send + 'static>(self, rng: R) -> Self {
+        Self {
+            rng: RngSource::Custom(Arc::new(CustomRng::new(rng))),
+            ..self
+        }
+    }
+
+    /// Returns a new constructor that gets timestamps from the specified
+    /// clock, rather than from the system clock.
+    ///
+    /// CUID creation panics if the clock returns an error, except with
+    /// [`try_create_id()`](Self::try_create_id), which returns it.
+    ///
+    /// ```
+    /// use cuid2::{CuidConstructor, FixedClock};
+    ///
+    /// let constructor = CuidConstructor::new().with_clock(FixedClock::new(0));
+    /// assert_eq!(24, constructor.create_id().len());
+    /// ```
+    pub fn with_clock<C: Clock + 'static>(self, clock: C) -> Self {
+        Self {
+            clock: ClockSource::Custom(Arc::new(clock)),
+            ..self
+        }
+    }
+
+    /// Returns a new constructor that shares its counter and fingerprint
+    /// with the given scope.
+    ///
+    /// See [`GeneratorScope`] for details.
+    #[cfg(feature = "std")]
+    pub fn with_scope(self, scope: GeneratorScope) -> Self {
+        Self { scope, ..self }
+    }
+
     /// Sets the length for CUIDs generated by this constrctor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length is less than [`MIN_LENGTH`](Self::MIN_LENGTH) or
+    /// greater than [`MAX_LENGTH`](Self::MAX_LENGTH).
     pub fn set_length(&mut self, length: u16) {
+        check_length(length).unwrap_or_else(|err| panic!("{err}"));
         self.length = length;
     }
 
-    /// Sets the counter function for this constructor.
-    pub fn set_counter(&mut self, counter: fn() -> u64) {
-        self.counter = counter;
+    /// Sets the counter for this constructor.
+    pub fn set_counter<C: Counter + 'static>(&mut self, counter: C) {
+        self.counter = CounterSource::Custom(Arc::new(counter));
+    }
+
+    /// Sets the fingerprinter for this constructor.
+    pub fn set_fingerprinter<F: Fingerprint + 'static>(&mut self, fingerprinter: F) {
+        self.fingerprinter = FingerprintSource::Custom(Arc::new(fingerprinter));
+    }
+
+    /// Sets the random number generator for this constructor.
+    ///
+    /// See [`with_rng()`](Self::with_rng) for details.
+    pub fn set_rng<R: RngCore + CryptoRng + Send + 'static>(&mut self, rng: R) {
+        self.rng = RngSource::Custom(Arc::new(CustomRng::new(rng)));
     }
 
-    /// Sets the fingerperinter function for this constructor.
-    pub fn set_fingerprinter(&mut self, fingerprinter: fn() -> String) {
-        self.fingerprinter = fingerprinter;
+    /// Sets the scope of this constructor's counter and fingerprint.
+    ///
+    /// See [`GeneratorScope`] for details.
+    #[cfg(feature = "std")]
+    pub fn set_scope(&mut self, scope: GeneratorScope) {
+        self.scope = scope;
+    }
+
+    /// Sets the clock for this constructor.
+    pub fn set_clock<C: Clock + 'static>(&mut self, clock: C) {
+        self.clock = ClockSource::Custom(Arc::new(clock));
     }
 
     /// Creates a new CUID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a custom clock or fingerprinter returns an error. See
+    /// [`try_create_id()`](Self::try_create_id) for a fallible version.
     #[inline]
     pub fn create_id(&self) -> String {
-        let time = get_timestamp();
+        let mut buf = [0; MAX_ID_LEN];
+        expect_id(self.write_into(&mut buf)).to_owned()
+    }
 
-        let entropy = create_entropy(self.length);
+    /// Creates a new CUID, or returns an error if the clock or fingerprinter
+    /// fails.
+    ///
+    /// ```
+    /// use cuid2::{Clock, ClockError, CuidConstructor, Error};
+    ///
+    /// struct Broken;
+    ///
+    /// impl Clock for Broken {
+    ///     fn now_millis(&self) -> Result<u64, ClockError> {
+    ///         Err(ClockError::new("no clock"))
+    ///     }
+    /// }
+    ///
+    /// assert!(CuidConstructor::new().try_create_id().is_ok());
+    ///
+    /// let broken = CuidConstructor::new().with_clock(Broken);
+    /// assert!(matches!(broken.try_create_id(), Err(Error::Clock(_))));
+    /// ```
+    #[inline]
+    pub fn try_create_id(&self) -> Result<String, Error> {
+        let mut buf = [0; MAX_ID_LEN];
+        self.write_into(&mut buf).map(ToOwned::to_owned)
+    }
 
-        let count = (self.counter)().to_base_36();
+    /// Creates a new CUID in the provided buffer, without allocating.
+    ///
+    /// Returns the portion of the buffer containing the CUID.
+    ///
+    /// ```
+    /// use cuid2::CuidConstructor;
+    ///
+    /// let mut buf = [0; 24];
+    /// let id = CuidConstructor::new().create_id_into(&mut buf);
+    ///
+    /// assert!(cuid2::is_cuid2(id));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer is shorter than the configured length.
+    #[inline]
+    pub fn create_id_into<'b>(&self, buf: &'b mut [u8]) -> &'b str {
+        assert!(
+            buf.len() >= usize::from(self.length),
+            "buffer of length {} is too small for a CUID of length {}",
+            buf.len(),
+            self.length
+        );
+        expect_id(self.write_into(buf))
+    }
 
-        let fingerprint = (self.fingerprinter)();
+    /// Creates a new CUID and writes it to `writer`, without allocating.
+    ///
+    /// ```
+    /// use std::fmt::Write;
+    ///
+    /// use cuid2::CuidConstructor;
+    ///
+    /// let mut out = String::from("id=");
+    /// CuidConstructor::new().write_id(&mut out).unwrap();
+    ///
+    /// assert_eq!(27, out.len());
+    /// ```
+    #[inline]
+    pub fn write_id<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        let mut buf = [0; MAX_ID_LEN];
+        writer.write_str(expect_id(self.write_into(&mut buf)))
+    }
 
-        // Construct the main part of the ID body by hashing the various inputs
-        let id_body = hash(
-            [
-                time.as_bytes(),
-                entropy.as_bytes(),
-                count.as_bytes(),
-                fingerprint.as_bytes(),
-            ],
-            // The hash should be the desired total length minus 1 character
-            // for the starting char.
-            self.length - 1,
+    /// Creates a new CUID stored inline, without allocating.
+    ///
+    /// ```
+    /// use cuid2::CuidConstructor;
+    ///
+    /// let id = CuidConstructor::new().create_inline_id();
+    /// let copy = id;
+    ///
+    /// assert_eq!(id, copy);
+    /// assert!(cuid2::is_cuid2(id));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configured length is greater than
+    /// [`InlineCuid2::CAPACITY`].
+    #[inline]
+    pub fn create_inline_id(&self) -> InlineCuid2 {
+        assert!(
+            usize::from(self.length) <= InlineCuid2::CAPACITY,
+            "CUID length {} is too long for an InlineCuid2, which holds at most {}",
+            self.length,
+            InlineCuid2::CAPACITY
         );
+        let mut buf = [0; InlineCuid2::CAPACITY];
+        let len = expect_id(self.write_into(&mut buf)).len();
+        InlineCuid2::from_buf(buf, len)
+    }
+
+    /// Creates a new CUID at the start of `buf`, returning the written portion.
+    ///
+    /// `buf` must be at least as long as the configured length.
+    fn write_into<'b>(&self, buf: &'b mut [u8]) -> Result<&'b str, Error> {
+        self.write_with_hasher(&mut Sha3_512::new(), buf)
+    }
 
-        // TODO check if index access makes a perf difference here
-        let first_letter = (*STARTING_CHARS
+    /// Like [`write_into()`](Self::write_into), but reuses `hasher`, which is
+    /// reset afterwards.
+    fn write_with_hasher<'b>(
+        &self,
+        hasher: &mut Sha3_512,
+        buf: &'b mut [u8],
+    ) -> Result<&'b str, Error> {
+        match &self.rng {
+            #[cfg(feature = "std")]
+            RngSource::Thread => {
+                self.write_with(hasher, &mut thread_rng(), &mut self.scoped_state(), buf)
+            }
+            RngSource::Custom(custom) => custom.with(|rng, seeded| {
+                self.write_with(hasher, rng, &mut DefaultState::Seeded(seeded), buf)
+            }),
+        }
+    }
+
+    /// Calls `f` with the RNG and the default counter and fingerprint, so
+    /// that they can be reused to create many CUIDs.
+    ///
+    /// With a custom RNG, its lock is held until `f` returns. With the
+    /// thread-local RNG and scope, the thread's state is borrowed (and checked
+    /// for a fork) once until `f` returns, unless a custom clock, counter, or
+    /// fingerprinter is configured, since those could themselves create CUIDs
+    /// on this thread.
+    fn with_sources<T>(&self, f: impl FnOnce(&mut dyn RngCore, &mut DefaultState<'_>) -> T) -> T {
+        match &self.rng {
+            #[cfg(feature = "std")]
+            RngSource::Thread => {
+                match (self.scope, &self.clock, &self.counter, &self.fingerprinter) {
+                    (
+                        GeneratorScope::Thread,
+                        ClockSource::System,
+                        CounterSource::Default,
+                        FingerprintSource::Default,
+                    ) => scope::with_thread_state(|state| {
+                        f(&mut thread_rng(), &mut DefaultState::Thread(state))
+                    }),
+                    _ => f(&mut thread_rng(), &mut self.scoped_state()),
+                }
+            }
+            RngSource::Custom(custom) => {
+                custom.with(|rng, seeded| f(rng, &mut DefaultState::Seeded(seeded)))
+            }
+        }
+    }
+
+    /// Returns the default counter and fingerprint for the configured scope,
+    /// for use with the thread-local RNG.
+    #[cfg(feature = "std")]
+    fn scoped_state(&self) -> DefaultState<'static> {
+        match self.scope {
+            GeneratorScope::Thread => DefaultState::ThreadLocal,
+            GeneratorScope::Process => DefaultState::Process(ProcessState::get()),
+        }
+    }
+
+    /// Creates a new CUID with the given RNG at the start of `buf`.
+    ///
+    /// Unless a custom counter or fingerprinter is configured, the counter and
+    /// fingerprint come from `defaults`. The hasher is reset afterwards, so
+    /// that it may be reused.
+    ///
+    /// The clock and fingerprinter are called before anything is hashed or
+    /// counted, so that on error the hasher is untouched and no count is
+    /// consumed.
+    fn write_with<'b, R: Rng + ?Sized>(
+        &self,
+        hasher: &mut Sha3_512,
+        rng: &mut R,
+        defaults: &mut DefaultState<'_>,
+        buf: &'b mut [u8],
+    ) -> Result<&'b str, Error> {
+        let timestamp = match &self.clock {
+            #[cfg(feature = "std")]
+            ClockSource::System => SystemClock.now_millis(),
+            ClockSource::Custom(clock) => clock.now_millis(),
+        }?;
+
+        let custom_fingerprint = match &self.fingerprinter {
+            FingerprintSource::Custom(fingerprinter) => Some(fingerprinter.fingerprint()?),
+            FingerprintSource::Default => None,
+        };
+
+        let custom_count = match &self.counter {
+            CounterSource::Custom(counter) => Some(counter.next_count()),
+            CounterSource::Default => None,
+        };
+
+        // Construct the main part of the ID body by hashing the various inputs
+        hasher.update(to_base_36_in(timestamp, &mut [0; MAX_BASE_36_LEN]));
+        hash_entropy(hasher, self.length, rng);
+        defaults.hash_count_and_fingerprint(hasher, custom_count, custom_fingerprint.as_deref());
+
+        // Panic safety: choose() only returns None if the slice is empty,
+        // and STARTING_CHARS is a statically defined non-empty slice.
+        let first_letter = *STARTING_CHARS
             .as_bytes()
-            // Panic safety: choose() only returns None if the slice is empty,
-            // and STARTING_CHARS is a statically defined non-empty slice.
-            .choose(&mut thread_rng())
-            .expect("STARTING_CHARS cannot be empty")) as char;
+            .choose(rng)
+            .expect("STARTING_CHARS cannot be empty");
+
+        Ok(finish_id(hasher, first_letter, self.length, buf))
+    }
+
+    /// Creates a new CUID as a validated [`Cuid2`].
+    #[inline]
+    pub fn create_cuid2(&self) -> Cuid2 {
+        Cuid2::from_string_unchecked(self.create_id())
+    }
+}
+
+/// Writes a CUID with the given first letter and a body from the hashed
+/// inputs at the start of `buf`, returning the written portion.
+///
+/// The hasher is reset, so that it may be reused.
+fn finish_id<'b>(
+    hasher: &mut Sha3_512,
+    first_letter: u8,
+    length: u16,
+    buf: &'b mut [u8],
+) -> &'b str {
+    // The hash should be the desired total length minus 1 character for the
+    // starting char. The length is at least MIN_LENGTH, so this cannot
+    // underflow.
+    //
+    // The reference implementation takes `hash(input).substring(1, length)`,
+    // skipping the hash's first two digits (see `hash()`). We take the leading
+    // digits instead, which are just as uniformly distributed.
+    let mut hash_buf = [0; DIGEST_BASE_36_BUF_LEN];
+    let hash = digest_to_base_36_in(
+        &hasher.finalize_reset().into(),
+        usize::from(length - 1),
+        &mut hash_buf,
+    );
+    let body_len = hash.len();
+
+    buf[0] = first_letter;
+    buf[1..=body_len].copy_from_slice(hash.as_bytes());
+
+    // SAFETY: the first letter is an ASCII letter, and the rest of this
+    // portion of the buffer contains only ASCII digits and letters
+    unsafe { core::str::from_utf8_unchecked(&buf[..=body_len]) }
+}
 
-        // Return only the requested length
-        format!("{first_letter}{id_body}")
+/// Checks that a CUID length is from `MIN_LENGTH` to `MAX_LENGTH`.
+fn check_length(length: u16) -> Result<(), Error> {
+    match length {
+        CuidConstructor::MIN_LENGTH..=CuidConstructor::MAX_LENGTH => Ok(()),
+        _ => Err(Error::InvalidLength { length }),
     }
 }
+
+/// Unwraps a newly created CUID for the infallible creation methods.
+fn expect_id(result: Result<&str, Error>) -> &str {
+    // Panic safety: the system clock only fails if the system time is before
+    // 1970-01-01. It is impossible on Unix systems to set a time before then,
+    // since the entire system uses a 32 or 64 bit unsigned integer for time,
+    // where zero is midnight 1970-01-01.
+    //
+    // If you are on a system that for some reason both can be and needs to be
+    // set >50 years in the past AND this library not working is a problem for
+    // you, please feel free to reach out.
+    //
+    // Custom clocks and fingerprinters document that their errors cause a
+    // panic, and the fallible methods return them instead.
+    result.unwrap_or_else(|err| panic!("{err}! Cannot continue"))
+}
+
+/// Where a constructor gets its counter values.
+#[derive(Clone)]
+enum CounterSource {
+    /// The thread-local counter, or the counter derived from a custom RNG.
+    Default,
+    /// A user-provided counter.
+    Custom(Arc<dyn Counter>),
+}
+
+impl fmt::Debug for CounterSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Default => "Default",
+            Self::Custom(_) => "Custom",
+        })
+    }
+}
+
+/// Where a constructor gets its timestamps.
+#[derive(Clone)]
+enum ClockSource {
+    /// The system clock.
+    #[cfg(feature = "std")]
+    System,
+    /// A user-provided clock.
+    Custom(Arc<dyn Clock>),
+}
+
+impl fmt::Debug for ClockSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            #[cfg(feature = "std")]
+            Self::System => "System",
+            Self::Custom(_) => "Custom",
+        })
+    }
+}
+
+/// Where a constructor gets its fingerprint.
+#[derive(Clone)]
+enum FingerprintSource {
+    /// The thread-local fingerprint, or the fingerprint derived from a custom
+    /// RNG. Either is hashed in place rather than being cloned.
+    Default,
+    /// A user-provided fingerprinter.
+    Custom(Arc<dyn Fingerprint>),
+}
+
+impl fmt::Debug for FingerprintSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Default => "Default",
+            Self::Custom(_) => "Custom",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
 impl Default for CuidConstructor {
     fn default() -> Self {
         Self::new()
@@ -386,56 +989,368 @@ impl Default for CuidConstructor {
 /// Use a static constructor for create_id() so that we don't need to pay the
 /// (minimal, probably trivial) cost of constructor creation when called via
 /// `create_id()`.
+#[cfg(feature = "std")]
 static DEFAULT_CONSTRUCTOR: CuidConstructor = CuidConstructor::new();
 
 /// Creates a new CUID.
+#[cfg(feature = "std")]
 #[inline]
 pub fn create_id() -> String {
     DEFAULT_CONSTRUCTOR.create_id()
 }
 
+/// Creates a new CUID, or returns an error if the system clock is set to
+/// before the Unix epoch.
+///
+/// ```
+/// let id = cuid2::try_create_id()?;
+/// assert_eq!(24, id.len());
+/// # Ok::<(), cuid2::Error>(())
+/// ```
+#[cfg(feature = "std")]
+#[inline]
+pub fn try_create_id() -> Result<String, Error> {
+    DEFAULT_CONSTRUCTOR.try_create_id()
+}
+
+/// Creates a new CUID as a validated [`Cuid2`].
+#[cfg(feature = "std")]
+#[inline]
+pub fn create_cuid2() -> Cuid2 {
+    DEFAULT_CONSTRUCTOR.create_cuid2()
+}
+
 /// Creates a new CUID.
 ///
 /// Alias for `created_id()`, which is the interface defined in the reference
 /// implementation. The `cuid()` interface allows easier drop-in replacement
 /// for crates using the v1 `cuid` crate.
+#[cfg(feature = "std")]
 #[inline]
 pub fn cuid() -> String {
     create_id()
 }
 
+/// Creates a CUID deterministically from the given inputs, rather than from a
+/// clock, counter, fingerprinter, and RNG.
+///
+/// The timestamp, entropy, counter, and fingerprint are hashed exactly as they
+/// are for any other CUID, and the ID is the first letter followed by the
+/// leading `length - 1` digits of the hash. This is intended for checking
+/// conformance with other implementations, and for reproducing an ID from
+/// known inputs. Since the entropy is supplied by the caller, IDs created
+/// this way are only as unpredictable as it is.
+///
+/// ```
+/// let id = cuid2::create_id_from_parts(
+///     1_700_000_000_000,
+///     "k3j5n8q0w2e4r6t8y0u2i4o6",
+///     12345,
+///     "gqz1cxk6bn4h1k5ezxlw2xlt9k9m4d0n",
+///     'c',
+///     24,
+/// )?;
+///
+/// assert_eq!("c74ibrx3plgbnjezjx0qw5gi", id);
+/// # Ok::<(), cuid2::Error>(())
+/// ```
+///
+/// Returns an error if the length is invalid, or if the first letter is not a
+/// lowercase ASCII letter.
+pub fn create_id_from_parts(
+    timestamp: u64,
+    entropy: &str,
+    counter: u64,
+    fingerprint: &str,
+    first_letter: char,
+    length: u16,
+) -> Result<String, Error> {
+    check_length(length)?;
+    if !first_letter.is_ascii_lowercase() {
+        return Err(Error::InvalidFirstLetter {
+            letter: first_letter,
+        });
+    }
+
+    let mut num_buf = [0; MAX_BASE_36_LEN];
+    let mut hasher = Sha3_512::new();
+    hasher.update(to_base_36_in(timestamp, &mut num_buf));
+    hasher.update(entropy);
+    hasher.update(to_base_36_in(counter, &mut num_buf));
+    hasher.update(fingerprint);
+
+    let mut buf = [0; MAX_ID_LEN];
+    // The letter is ASCII, so it fits in a byte
+    Ok(finish_id(&mut hasher, first_letter as u8, length, &mut buf).to_owned())
+}
+
 #[cfg(test)]
 mod test {
-    use std::{collections::HashSet, thread};
+    #[cfg(feature = "std")]
+    use num::bigint;
 
     use super::*;
 
+    /// Asserts that each base36 digit is equally likely in the entropy, with a
+    /// chi-square test.
     #[test]
+    fn entropy_is_uniform() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        let mut counts = [0_u64; 36];
+        let mut buf = [0; 32];
+        for _ in 0..10_000 {
+            fill_entropy(&mut buf, &mut rng);
+            for digit in buf {
+                let value = BASE_36_DIGITS.iter().position(|d| *d == digit).unwrap();
+                counts[value] += 1;
+            }
+        }
+
+        let expected = (10_000 * buf.len() / counts.len()) as f64;
+        let chi_square: f64 = counts
+            .iter()
+            .map(|&count| (count as f64 - expected).powi(2) / expected)
+            .sum();
+        // The critical value for 35 degrees of freedom at p = 0.001
+        assert!(chi_square < 66.62, "chi-square {chi_square}: {counts:?}");
+    }
+
+    #[test]
+    fn entropy_rejects_biased_bytes() {
+        /// Yields each byte in turn.
+        struct Bytes(alloc::vec::IntoIter<u8>);
+
+        impl RngCore for Bytes {
+            fn next_u32(&mut self) -> u32 {
+                unimplemented!()
+            }
+
+            fn next_u64(&mut self) -> u64 {
+                unimplemented!()
+            }
+
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                dest.iter_mut()
+                    .for_each(|byte| *byte = self.0.next().unwrap());
+            }
+
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+                self.fill_bytes(dest);
+                Ok(())
+            }
+        }
+
+        let mut rng = Bytes(alloc::vec![255, 0, 252, 35, 36, 251, 253].into_iter());
+        let mut buf = [0; 4];
+        fill_entropy(&mut buf, &mut rng);
+        assert_eq!(b"0z0z", &buf);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn non_allocating_variants() {
+        let constructor = CuidConstructor::new().with_length(10);
+
+        let mut buf = [0; 10];
+        assert_eq!(10, constructor.create_id_into(&mut buf).len());
+        assert!(is_cuid2(constructor.create_id_into(&mut buf)));
+
+        let mut out = String::new();
+        constructor.write_id(&mut out).unwrap();
+        assert_eq!(10, out.len());
+        assert!(is_cuid2(&out));
+
+        assert_eq!(10, constructor.create_inline_id().len());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[should_panic(expected = "too small")]
+    fn create_id_into_short_buffer() {
+        CuidConstructor::new().create_id_into(&mut [0; 23]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[should_panic(expected = "too long for an InlineCuid2")]
+    fn create_inline_id_too_long() {
+        CuidConstructor::new().with_length(33).create_inline_id();
+    }
+
+    #[test]
+    fn constructor_is_shareable() {
+        fn assert_shareable<T: Send + Sync + Clone + fmt::Debug>() {}
+        assert_shareable::<CuidConstructor>();
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn custom_counter_and_fingerprinter() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        let count = Arc::new(AtomicU64::new(0));
+        let tenant = String::from("tenant-1");
+        let constructor = CuidConstructor::new()
+            .with_counter({
+                let count = Arc::clone(&count);
+                move || count.fetch_add(1, Ordering::Relaxed)
+            })
+            .with_fingerprinter(move || tenant.clone());
+
+        let clone = constructor.clone();
+        assert!(is_cuid2(constructor.create_id()));
+        assert!(is_cuid2(clone.create_id()));
+        assert_eq!(2, count.load(Ordering::Relaxed));
+        assert_eq!(
+            "CuidConstructor { length: 24, counter: Custom, fingerprinter: Custom, rng: Thread, clock: System, scope: Thread }",
+            format!("{constructor:?}")
+        );
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn is_cuid2_bytes_matches_validator(id in "[a-z0-9A-Z_é]{0,78}") {
+            let expected = Validator::new().is_valid(&id);
+            assert_eq!(expected, is_cuid2_bytes(id.as_bytes()));
+            assert_eq!(expected, is_cuid2(&id));
+        }
+
+        #[test]
+        fn is_cuid2_bytes_rejects_invalid_utf8(bytes in proptest::collection::vec(proptest::arbitrary::any::<u8>(), 0..78)) {
+            let expected = core::str::from_utf8(&bytes).is_ok_and(|id| Validator::new().is_valid(id));
+            assert_eq!(expected, is_cuid2_bytes(&bytes));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn validate_many_returns_invalid_indices() {
+        let mut ids = CuidConstructor::new().create_ids(100);
+        ids[3].push('!');
+        ids[50].truncate(1);
+        ids[99].make_ascii_uppercase();
+        assert_eq!(vec![3, 50, 99], validate_many(&ids));
+        assert!(validate_many::<&str>(&[]).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn length_is_validated() {
+        for length in [0, 1, 77, u16::MAX] {
+            assert!(matches!(
+                CuidConstructor::new().try_with_length(length),
+                Err(Error::InvalidLength { length: l }) if l == length
+            ));
+        }
+        for length in [2, 32, 76] {
+            let constructor = CuidConstructor::new().try_with_length(length).unwrap();
+            for _ in 0..100 {
+                let id = constructor.create_id();
+                assert_eq!(usize::from(length), id.len());
+                assert!(is_cuid2(id));
+            }
+        }
+    }
+
+    #[test]
+    fn reliable_digest_len() {
+        // 2^384: every hash at least this large has at least as many digits
+        let mut digest = [0; 64];
+        digest[15] = 1;
+        let mut buf = [0; DIGEST_BASE_36_BUF_LEN];
+        let digits = digest_to_base_36_in(&digest, usize::MAX, &mut buf);
+        assert_eq!(RELIABLE_DIGEST_BASE_36_LEN, digits.len());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[should_panic(expected = "Invalid CUID length 1")]
+    fn with_invalid_length() {
+        CuidConstructor::new().with_length(1);
+    }
+
+    /// A fingerprinter that always fails.
+    #[cfg(feature = "std")]
+    struct Unavailable;
+
+    #[cfg(feature = "std")]
+    impl Fingerprint for Unavailable {
+        fn fingerprint(&self) -> Result<alloc::borrow::Cow<'_, str>, FingerprintError> {
+            Err(FingerprintError::new("unavailable"))
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn failed_fingerprint_is_returned() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        let count = Arc::new(AtomicU64::new(0));
+        let constructor = CuidConstructor::new()
+            .with_counter({
+                let count = Arc::clone(&count);
+                move || count.fetch_add(1, Ordering::Relaxed)
+            })
+            .with_fingerprinter(Unavailable);
+
+        assert!(matches!(
+            constructor.try_create_id(),
+            Err(Error::Fingerprint(_))
+        ));
+        // No count is consumed by a failed CUID
+        assert_eq!(0, count.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[should_panic(expected = "Failed to create fingerprint: unavailable! Cannot continue")]
+    fn failed_fingerprint_panics() {
+        CuidConstructor::new()
+            .with_fingerprinter(Unavailable)
+            .create_id();
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
     fn counter_increments() {
-        let start = get_count();
-        let next = get_count();
+        let start = scope::with_thread_state(|state| state.next_count());
+        let next = scope::with_thread_state(|state| state.next_count());
 
         // concurrent test may have also incremented
         assert!(next > start);
     }
 
+    #[test]
+    fn is_cuid2_rejects_invalid_chars() {
+        assert!(is_cuid2("ab1"));
+        assert!(!is_cuid2("a#1"));
+        assert!(!is_cuid2("aB1"));
+        assert!(!is_cuid2("éa"));
+    }
+
     #[test]
     #[ignore] // slow: run explicitly when desired
+    #[cfg(feature = "rayon")]
     fn collisions() {
-        // generate ~10e6 IDs across all available cores
-        let cores = num_cpus::get();
-        let per_core = 10_000_000 / cores;
+        assert_no_collisions(&CuidConstructor::new());
+    }
+
+    #[test]
+    #[ignore] // slow: run explicitly when desired
+    #[cfg(feature = "rayon")]
+    fn collisions_process_scope() {
+        assert_no_collisions(&CuidConstructor::new().with_scope(GeneratorScope::Process));
+    }
 
-        // collect to force spawning the threads instead of just holding them lazily
-        #[allow(clippy::needless_collect)]
-        let threads = (0..cores)
-            .map(|_| thread::spawn(move || (0..per_core).map(|_| create_id()).collect::<Vec<_>>()))
-            .collect::<Vec<_>>();
+    #[cfg(feature = "rayon")]
+    fn assert_no_collisions(constructor: &CuidConstructor) {
+        use std::collections::HashSet;
 
-        let res = threads
-            .into_iter()
-            .flat_map(|handle| handle.join().unwrap())
-            .collect::<Vec<_>>();
+        // generate ~10e6 IDs across all available cores
+        let res = constructor.par_create_ids(10_000_000);
 
         // All IDs are unique
         assert_eq!(res.iter().collect::<HashSet<_>>().len(), res.len())
@@ -448,6 +1363,7 @@ mod test {
     /// and https://github.com/paralleldrive/cuid2/blob/b5665387fdf7f947e135f030a545df22c5010a7d/src/histogram.js
     #[test]
     #[ignore] // slow: run explicitly when desired
+    #[cfg(feature = "std")]
     fn distribution() {
         let count = 1_000_000;
 
@@ -457,7 +1373,7 @@ mod test {
         let histogram = (0..count)
             .map(|_| create_id())
             // parse the ID (minus starting char) as a base36 number
-            .map(|id| bigint::BigUint::parse_bytes(id[1..].as_bytes(), 36).unwrap())
+            .map(|id| bigint::BigUint::parse_bytes(&id.as_bytes()[1..], 36).unwrap())
             // Determine its bucket number.
             // We know the bucket number will be <20, so we .to_u32_digits()
             // and grab what should be the only item.
@@ -0,0 +1,311 @@
+//! Lossless packing of CUIDs into 128 bits
+
+use alloc::string::String;
+use core::fmt;
+
+use cuid_util::BASE_36_DIGITS;
+
+use crate::{Cuid2, Cuid2Str, CuidConstructor};
+
+/// The longest CUID that can be packed into 128 bits.
+///
+/// There are `26 * 36^23` CUIDs of length 24, which is less than `2^124`, but
+/// `26 * 36^24` of length 25, which is more than `2^128`.
+pub const MAX_PACKED_LEN: usize = 24;
+
+/// The number of CUIDs shorter than each length: `OFFSETS[len]` is the packed
+/// value of the first CUID of length `len`.
+///
+/// CUIDs are packed in order of length, then in lexicographic order within a
+/// length, so `OFFSETS[MAX_PACKED_LEN + 1]` is the number of packable CUIDs.
+const OFFSETS: [u128; MAX_PACKED_LEN + 2] = {
+    let mut offsets = [0; MAX_PACKED_LEN + 2];
+    let mut len = CuidConstructor::MIN_LENGTH as usize + 1;
+    while len < offsets.len() {
+        // Add the number of CUIDs of length `len - 1`
+        offsets[len] = offsets[len - 1] + 26 * 36_u128.pow(len as u32 - 2);
+        len += 1;
+    }
+    offsets
+};
+
+impl Cuid2Str {
+    /// Packs the CUID into a `u128`, or returns an error if it is longer than
+    /// [`MAX_PACKED_LEN`].
+    ///
+    /// The packing is exact, and preserves the order of CUIDs with the same
+    /// length. Shorter CUIDs pack to smaller values.
+    pub fn to_u128(&self) -> Result<u128, PackCuid2Error> {
+        let bytes = self.as_bytes();
+        if bytes.len() > MAX_PACKED_LEN {
+            return Err(PackCuid2Error::TooLong {
+                length: bytes.len(),
+            });
+        }
+        // The first byte is a letter, and the rest are base36 digits, which
+        // sort in the same order as their ASCII bytes.
+        let first = u128::from(bytes[0] - b'a');
+        let index = bytes[1..].iter().fold(first, |acc, byte| {
+            let digit = match byte {
+                b'0'..=b'9' => byte - b'0',
+                _ => byte - b'a' + 10,
+            };
+            acc * 36 + u128::from(digit)
+        });
+        Ok(OFFSETS[bytes.len()] + index)
+    }
+
+    /// Packs the CUID into 16 big-endian bytes, or returns an error if it is
+    /// longer than [`MAX_PACKED_LEN`].
+    ///
+    /// Comparing the bytes compares the packed values, so this is suitable
+    /// for a binary or UUID database column.
+    pub fn to_bytes(&self) -> Result<[u8; 16], PackCuid2Error> {
+        self.to_u128().map(u128::to_be_bytes)
+    }
+}
+
+impl Cuid2 {
+    /// Packs the CUID into a `u128`, or returns an error if it is longer than
+    /// [`MAX_PACKED_LEN`].
+    ///
+    /// This is exact, so [`from_u128()`](Self::from_u128) returns the same
+    /// CUID. With the `uuid` feature, [`to_uuid()`](Self::to_uuid) and
+    /// [`from_uuid()`](Self::from_uuid) use it to store CUIDs in UUID columns.
+    ///
+    /// ```
+    /// use cuid2::Cuid2;
+    ///
+    /// let id = cuid2::create_cuid2();
+    /// let packed = id.to_u128()?;
+    /// assert_eq!(id, Cuid2::from_u128(packed)?);
+    ///
+    /// let long = cuid2::CuidConstructor::new().with_length(32).create_cuid2();
+    /// assert!(long.to_u128().is_err());
+    /// # Ok::<(), cuid2::PackCuid2Error>(())
+    /// ```
+    pub fn to_u128(&self) -> Result<u128, PackCuid2Error> {
+        self.as_cuid2_str().to_u128()
+    }
+
+    /// Unpacks a CUID packed with [`to_u128()`](Self::to_u128), or returns an
+    /// error if the value is too large to be a packed CUID.
+    pub fn from_u128(value: u128) -> Result<Self, PackCuid2Error> {
+        if value >= OFFSETS[MAX_PACKED_LEN + 1] {
+            return Err(PackCuid2Error::OutOfRange);
+        }
+        // Panic safety: the value is below the last offset, and the offset
+        // of the minimum length is zero.
+        let len = (0..=MAX_PACKED_LEN)
+            .rev()
+            .find(|&len| OFFSETS[len] <= value)
+            .expect("value is in range");
+
+        let mut index = value - OFFSETS[len];
+        let mut bytes = [0; MAX_PACKED_LEN];
+        for byte in bytes[1..len].iter_mut().rev() {
+            *byte = BASE_36_DIGITS[(index % 36) as usize];
+            index /= 36;
+        }
+        // What remains is less than 26, since there are 26 * 36^(len - 1)
+        // CUIDs of this length.
+        bytes[0] = b'a' + index as u8;
+
+        // Panic safety: the bytes are all ASCII letters and digits.
+        let id = core::str::from_utf8(&bytes[..len]).expect("bytes are ASCII");
+        Ok(Self::from_string_unchecked(String::from(id)))
+    }
+
+    /// Packs the CUID into 16 big-endian bytes, or returns an error if it is
+    /// longer than [`MAX_PACKED_LEN`].
+    ///
+    /// See [`Cuid2Str::to_bytes()`].
+    pub fn to_bytes(&self) -> Result<[u8; 16], PackCuid2Error> {
+        self.as_cuid2_str().to_bytes()
+    }
+
+    /// Unpacks a CUID packed with [`to_bytes()`](Self::to_bytes), or returns
+    /// an error if the bytes are not a packed CUID.
+    pub fn from_bytes(bytes: [u8; 16]) -> Result<Self, PackCuid2Error> {
+        Self::from_u128(u128::from_be_bytes(bytes))
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl Cuid2Str {
+    /// Packs the CUID into a [`Uuid`](uuid::Uuid), or returns an error if it
+    /// is longer than [`MAX_PACKED_LEN`].
+    ///
+    /// The UUID holds the packed value rather than a valid RFC 9562 version,
+    /// but it orders and compares like the CUID.
+    pub fn to_uuid(&self) -> Result<uuid::Uuid, PackCuid2Error> {
+        self.to_u128().map(uuid::Uuid::from_u128)
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl Cuid2 {
+    /// Packs the CUID into a [`Uuid`](uuid::Uuid), or returns an error if it
+    /// is longer than [`MAX_PACKED_LEN`].
+    ///
+    /// See [`Cuid2Str::to_uuid()`].
+    ///
+    /// ```
+    /// use cuid2::Cuid2;
+    ///
+    /// let id = cuid2::create_cuid2();
+    /// let uuid = id.to_uuid()?;
+    /// assert_eq!(id, Cuid2::from_uuid(uuid)?);
+    /// # Ok::<(), cuid2::PackCuid2Error>(())
+    /// ```
+    pub fn to_uuid(&self) -> Result<uuid::Uuid, PackCuid2Error> {
+        self.as_cuid2_str().to_uuid()
+    }
+
+    /// Unpacks a CUID packed with [`to_uuid()`](Self::to_uuid), or returns an
+    /// error if the UUID is not a packed CUID.
+    pub fn from_uuid(uuid: uuid::Uuid) -> Result<Self, PackCuid2Error> {
+        Self::from_u128(uuid.as_u128())
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl TryFrom<&Cuid2Str> for uuid::Uuid {
+    type Error = PackCuid2Error;
+
+    fn try_from(id: &Cuid2Str) -> Result<Self, Self::Error> {
+        id.to_uuid()
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl TryFrom<&Cuid2> for uuid::Uuid {
+    type Error = PackCuid2Error;
+
+    fn try_from(id: &Cuid2) -> Result<Self, Self::Error> {
+        id.to_uuid()
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl TryFrom<uuid::Uuid> for Cuid2 {
+    type Error = PackCuid2Error;
+
+    fn try_from(uuid: uuid::Uuid) -> Result<Self, Self::Error> {
+        Self::from_uuid(uuid)
+    }
+}
+
+/// The error returned when a CUID cannot be packed into, or unpacked from,
+/// 128 bits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PackCuid2Error {
+    /// The CUID is longer than [`MAX_PACKED_LEN`].
+    TooLong {
+        /// The length of the CUID.
+        length: usize,
+    },
+    /// The value is too large to be a packed CUID.
+    OutOfRange,
+}
+
+impl fmt::Display for PackCuid2Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLong { length } => write!(
+                f,
+                "CUID of length {length} cannot be packed: must be at most {MAX_PACKED_LEN}"
+            ),
+            Self::OutOfRange => f.write_str("value is too large to be a packed CUID"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PackCuid2Error {}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn cuid(id: &str) -> Cuid2 {
+        id.parse().unwrap()
+    }
+
+    proptest! {
+        #[test]
+        fn roundtrip(id in "[a-z][a-z0-9]{1,23}") {
+            let id = cuid(&id);
+            assert_eq!(id, Cuid2::from_u128(id.to_u128().unwrap()).unwrap());
+            assert_eq!(id, Cuid2::from_bytes(id.to_bytes().unwrap()).unwrap());
+        }
+
+        #[cfg(feature = "uuid")]
+        #[test]
+        fn uuid_roundtrip(id in "[a-z][a-z0-9]{1,23}") {
+            let id = cuid(&id);
+            let uuid = uuid::Uuid::try_from(&id).unwrap();
+            assert_eq!(id.to_u128().unwrap(), uuid.as_u128());
+            assert_eq!(id, Cuid2::try_from(uuid).unwrap());
+        }
+
+        #[test]
+        fn preserves_order(a in "[a-z][a-z0-9]{23}", b in "[a-z][a-z0-9]{23}") {
+            let (a, b) = (cuid(&a), cuid(&b));
+            assert_eq!(a.cmp(&b), a.to_bytes().unwrap().cmp(&b.to_bytes().unwrap()));
+        }
+    }
+
+    #[test]
+    fn packs_boundaries() {
+        let last = OFFSETS[MAX_PACKED_LEN + 1] - 1;
+        assert!(last < 1 << 124);
+        for (id, value) in [
+            ("a0", 0),
+            ("zz", 26 * 36 - 1),
+            ("a00", 26 * 36),
+            ("zzzzzzzzzzzzzzzzzzzzzzzz", last),
+        ] {
+            assert_eq!(value, cuid(id).to_u128().unwrap(), "{id}");
+            assert_eq!(id, Cuid2::from_u128(value).unwrap().as_str());
+        }
+    }
+
+    #[test]
+    fn rejects_unpackable() {
+        let long = "a".repeat(MAX_PACKED_LEN + 1);
+        assert_eq!(
+            Err(PackCuid2Error::TooLong {
+                length: MAX_PACKED_LEN + 1
+            }),
+            cuid(&long).to_u128()
+        );
+        assert_eq!(
+            Err(PackCuid2Error::OutOfRange),
+            Cuid2::from_u128(OFFSETS[MAX_PACKED_LEN + 1])
+        );
+        assert_eq!(
+            Err(PackCuid2Error::OutOfRange),
+            Cuid2::from_bytes([0xff; 16])
+        );
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn rejects_unpackable_uuids() {
+        let long = "a".repeat(MAX_PACKED_LEN + 1);
+        assert_eq!(
+            Err(PackCuid2Error::TooLong {
+                length: MAX_PACKED_LEN + 1
+            }),
+            cuid(&long).to_uuid()
+        );
+        assert_eq!(
+            Err(PackCuid2Error::OutOfRange),
+            Cuid2::from_uuid(uuid::Uuid::from_u128(u128::MAX))
+        );
+    }
+}
@@ -0,0 +1,409 @@
+//! CUIDs tagged with a type prefix, like `user_tz4a98xxat96iws9zmbrgj3a`
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    string::String,
+};
+use core::{fmt, str::FromStr};
+
+use crate::{expect_id, is_cuid2, Cuid2, CuidConstructor, Error, ParseCuid2Error, MAX_ID_LEN};
+
+/// The character separating a prefix from its CUID.
+const SEPARATOR: char = '_';
+
+/// Creates CUIDs tagged with a type prefix, such as `user_` or `org_`.
+///
+/// A prefix is 1 to [`MAX_PREFIX_LEN`](Self::MAX_PREFIX_LEN) lowercase ASCII
+/// letters and underscores, starting and ending with a letter. It is
+/// separated from the CUID by an underscore, and the CUID itself is created
+/// by the wrapped [`CuidConstructor`].
+///
+/// ```
+/// use cuid2::{CuidConstructor, PrefixedConstructor};
+///
+/// let users = CuidConstructor::new().with_prefix("user")?;
+/// let id = users.create_id();
+///
+/// assert!(id.starts_with("user_"));
+/// assert!(cuid2::is_prefixed_cuid2(&id, "user"));
+///
+/// let parsed = users.parse(&id)?;
+/// assert_eq!("user", parsed.prefix());
+/// assert_eq!(&id[5..], parsed.cuid());
+///
+/// assert!(PrefixedConstructor::new("User", CuidConstructor::new()).is_err());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct PrefixedConstructor {
+    prefix: Cow<'static, str>,
+    constructor: CuidConstructor,
+}
+
+impl PrefixedConstructor {
+    /// The maximum length of a prefix, not including the separator.
+    pub const MAX_PREFIX_LEN: usize = 63;
+
+    /// Creates a new constructor that tags CUIDs from `constructor` with
+    /// `prefix`, or returns an error if the prefix is invalid.
+    pub fn new<P: Into<Cow<'static, str>>>(
+        prefix: P,
+        constructor: CuidConstructor,
+    ) -> Result<Self, Error> {
+        let prefix = prefix.into();
+        if is_prefix(&prefix) {
+            Ok(Self {
+                prefix,
+                constructor,
+            })
+        } else {
+            Err(Error::InvalidPrefix)
+        }
+    }
+
+    /// Returns the prefix.
+    #[inline]
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Returns the constructor used to create the CUID after the prefix.
+    #[inline]
+    pub fn constructor(&self) -> &CuidConstructor {
+        &self.constructor
+    }
+
+    /// Creates a new prefixed CUID.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`CuidConstructor::create_id()`].
+    pub fn create_id(&self) -> String {
+        let mut buf = [0; MAX_ID_LEN];
+        self.join(expect_id(self.constructor.write_into(&mut buf)))
+    }
+
+    /// Creates a new prefixed CUID, or returns an error if the clock or
+    /// fingerprinter fails.
+    pub fn try_create_id(&self) -> Result<String, Error> {
+        let mut buf = [0; MAX_ID_LEN];
+        self.constructor
+            .write_into(&mut buf)
+            .map(|id| self.join(id))
+    }
+
+    /// Creates a new prefixed CUID as a validated [`PrefixedCuid2`].
+    pub fn create_prefixed_cuid2(&self) -> PrefixedCuid2 {
+        PrefixedCuid2 {
+            id: self.create_id(),
+            separator: self.prefix.len(),
+        }
+    }
+
+    /// Parses a prefixed CUID, checking that its prefix is this
+    /// constructor's prefix.
+    pub fn parse(&self, s: &str) -> Result<PrefixedCuid2, ParsePrefixedCuid2Error> {
+        let id: PrefixedCuid2 = s.parse()?;
+        if id.prefix() == self.prefix() {
+            Ok(id)
+        } else {
+            Err(ParsePrefixedCuid2Error::UnexpectedPrefix)
+        }
+    }
+
+    /// Joins the prefix and a CUID with the separator.
+    fn join(&self, id: &str) -> String {
+        let mut out = String::with_capacity(self.prefix.len() + 1 + id.len());
+        out.push_str(&self.prefix);
+        out.push(SEPARATOR);
+        out.push_str(id);
+        out
+    }
+}
+
+impl CuidConstructor {
+    /// Returns a constructor that tags CUIDs from this constructor with
+    /// `prefix`, or an error if the prefix is invalid.
+    ///
+    /// See [`PrefixedConstructor`] for details.
+    pub fn with_prefix<P: Into<Cow<'static, str>>>(
+        self,
+        prefix: P,
+    ) -> Result<PrefixedConstructor, Error> {
+        PrefixedConstructor::new(prefix, self)
+    }
+}
+
+/// An owned, validated CUID with a type prefix.
+///
+/// Like [`Cuid2`], comparison, ordering, and hashing all behave exactly as
+/// they do for the underlying string.
+///
+/// ```
+/// use cuid2::PrefixedCuid2;
+///
+/// let id: PrefixedCuid2 = "user_tz4a98xxat96iws9zmbrgj3a".parse().unwrap();
+/// assert_eq!("user", id.prefix());
+/// assert_eq!("tz4a98xxat96iws9zmbrgj3a", id.cuid());
+///
+/// assert!("tz4a98xxat96iws9zmbrgj3a".parse::<PrefixedCuid2>().is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PrefixedCuid2 {
+    id: String,
+    /// The index of the separator in `id`.
+    separator: usize,
+}
+
+impl PrefixedCuid2 {
+    /// Returns the whole ID as a string slice.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the prefix, without the separator.
+    #[inline]
+    pub fn prefix(&self) -> &str {
+        &self.id[..self.separator]
+    }
+
+    /// Returns the CUID following the prefix.
+    #[inline]
+    pub fn cuid(&self) -> &str {
+        &self.id[self.separator + 1..]
+    }
+
+    /// Consumes the ID, returning the prefix and the CUID.
+    pub fn into_parts(self) -> (String, Cuid2) {
+        let cuid = Cuid2::from_string_unchecked(self.cuid().to_owned());
+        let mut prefix = self.id;
+        prefix.truncate(self.separator);
+        (prefix, cuid)
+    }
+
+    /// Consumes the ID, returning the underlying `String`.
+    #[inline]
+    pub fn into_string(self) -> String {
+        self.id
+    }
+
+    /// Finds the separator in a prefixed CUID, validating both parts.
+    fn split(s: &str) -> Result<usize, ParsePrefixedCuid2Error> {
+        // A CUID never contains the separator, so the last one ends the prefix
+        let separator = s
+            .rfind(SEPARATOR)
+            .ok_or(ParsePrefixedCuid2Error::MissingSeparator)?;
+        if !is_prefix(&s[..separator]) {
+            return Err(ParsePrefixedCuid2Error::InvalidPrefix);
+        }
+        if !is_cuid2(&s[separator + 1..]) {
+            return Err(ParsePrefixedCuid2Error::InvalidCuid2(ParseCuid2Error(())));
+        }
+        Ok(separator)
+    }
+}
+
+impl fmt::Display for PrefixedCuid2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.id)
+    }
+}
+
+impl AsRef<str> for PrefixedCuid2 {
+    fn as_ref(&self) -> &str {
+        &self.id
+    }
+}
+
+impl From<PrefixedCuid2> for String {
+    fn from(id: PrefixedCuid2) -> Self {
+        id.id
+    }
+}
+
+impl FromStr for PrefixedCuid2 {
+    type Err = ParsePrefixedCuid2Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            separator: Self::split(s)?,
+            id: s.to_owned(),
+        })
+    }
+}
+
+impl TryFrom<&str> for PrefixedCuid2 {
+    type Error = ParsePrefixedCuid2Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl TryFrom<String> for PrefixedCuid2 {
+    type Error = ParsePrefixedCuid2Error;
+
+    /// Validates the string, reusing its allocation on success.
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Ok(Self {
+            separator: Self::split(&value)?,
+            id: value,
+        })
+    }
+}
+
+/// The error returned when a string is not a valid prefixed CUID.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParsePrefixedCuid2Error {
+    /// There is no separator between a prefix and a CUID.
+    MissingSeparator,
+    /// The prefix is not 1 to 63 lowercase ASCII letters and underscores,
+    /// starting and ending with a letter.
+    InvalidPrefix,
+    /// The prefix is valid, but not the expected one.
+    UnexpectedPrefix,
+    /// The part after the prefix is not a valid CUID.
+    InvalidCuid2(ParseCuid2Error),
+}
+
+impl fmt::Display for ParsePrefixedCuid2Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSeparator => {
+                f.write_str("invalid prefixed CUID2: expected a prefix followed by '_'")
+            }
+            Self::InvalidPrefix => f.write_str(INVALID_PREFIX),
+            Self::UnexpectedPrefix => f.write_str("invalid prefixed CUID2: unexpected prefix"),
+            Self::InvalidCuid2(err) => err.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParsePrefixedCuid2Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidCuid2(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseCuid2Error> for ParsePrefixedCuid2Error {
+    fn from(err: ParseCuid2Error) -> Self {
+        Self::InvalidCuid2(err)
+    }
+}
+
+/// Describes the rules for a valid prefix.
+pub(crate) const INVALID_PREFIX: &str = "invalid CUID prefix: expected 1 to 63 lowercase ASCII letters and underscores, starting and ending with a letter";
+
+/// Returns whether a string is a CUID with the expected prefix.
+///
+/// ```
+/// let id = cuid2::CuidConstructor::new().with_prefix("org").unwrap().create_id();
+///
+/// assert!(cuid2::is_prefixed_cuid2(&id, "org"));
+/// assert!(!cuid2::is_prefixed_cuid2(&id, "user"));
+/// assert!(!cuid2::is_prefixed_cuid2(&id[4..], "org"));
+/// ```
+#[inline]
+pub fn is_prefixed_cuid2<S: AsRef<str>>(to_check: S, expected_prefix: &str) -> bool {
+    is_prefix(expected_prefix)
+        && to_check
+            .as_ref()
+            .strip_prefix(expected_prefix)
+            .and_then(|rest| rest.strip_prefix(SEPARATOR))
+            .is_some_and(is_cuid2)
+}
+
+/// Returns whether a string is a valid prefix.
+fn is_prefix(prefix: &str) -> bool {
+    let bytes = prefix.as_bytes();
+    matches!(
+        (bytes.first(), bytes.last()),
+        (Some(first), Some(last)) if first.is_ascii_lowercase() && last.is_ascii_lowercase()
+    ) && bytes.len() <= PrefixedConstructor::MAX_PREFIX_LEN
+        && bytes
+            .iter()
+            .all(|&b| b.is_ascii_lowercase() || b == SEPARATOR as u8)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn prefixes_are_validated() {
+        for valid in ["a", "user", "api_key", &"a".repeat(63)] {
+            assert!(is_prefix(valid), "{valid}");
+        }
+        for invalid in [
+            "",
+            "User",
+            "user1",
+            "_user",
+            "user_",
+            "us-er",
+            &"a".repeat(64),
+        ] {
+            assert!(!is_prefix(invalid), "{invalid}");
+            assert!(matches!(
+                CuidConstructor::new().with_prefix(invalid.to_owned()),
+                Err(Error::InvalidPrefix)
+            ));
+        }
+    }
+
+    #[test]
+    fn created_ids_round_trip() {
+        let keys = CuidConstructor::new()
+            .with_length(10)
+            .with_prefix("api_key")
+            .unwrap();
+
+        let ids: HashSet<_> = (0..100).map(|_| keys.create_id()).collect();
+        assert_eq!(100, ids.len());
+        for id in &ids {
+            assert_eq!(18, id.len());
+            assert!(is_prefixed_cuid2(id, "api_key"));
+            assert!(!is_prefixed_cuid2(id, "api"));
+
+            let parsed = keys.parse(id).unwrap();
+            assert_eq!("api_key", parsed.prefix());
+            assert!(is_cuid2(parsed.cuid()));
+            assert_eq!(id.as_str(), parsed.as_str());
+        }
+
+        let id = keys.create_prefixed_cuid2();
+        let (prefix, cuid) = id.clone().into_parts();
+        assert_eq!(id.prefix(), prefix);
+        assert_eq!(id.cuid(), cuid.as_str());
+    }
+
+    #[test]
+    fn parse_rejects_invalid() {
+        use ParsePrefixedCuid2Error::*;
+
+        let cases = [
+            ("tz4a98xxat96iws9zmbrgj3a", MissingSeparator),
+            ("_tz4a98xxat96iws9zmbrgj3a", InvalidPrefix),
+            ("User_tz4a98xxat96iws9zmbrgj3a", InvalidPrefix),
+            ("user_", InvalidCuid2(ParseCuid2Error(()))),
+            ("user_1abc", InvalidCuid2(ParseCuid2Error(()))),
+        ];
+        for (s, err) in cases {
+            assert_eq!(Err(err), s.parse::<PrefixedCuid2>(), "{s}");
+        }
+
+        let users = CuidConstructor::new().with_prefix("user").unwrap();
+        assert_eq!(
+            Err(UnexpectedPrefix),
+            users.parse("org_tz4a98xxat96iws9zmbrgj3a")
+        );
+    }
+}
@@ -0,0 +1,294 @@
+//! Pluggable random number generation for CUID construction
+
+use alloc::{boxed::Box, string::String, sync::Arc};
+use core::fmt;
+
+use cuid_util::{to_base_36_in, MAX_BASE_36_LEN};
+#[cfg(feature = "std")]
+use rand::distributions::Standard;
+use rand::{distributions::Distribution, Rng, RngCore};
+use sha3::{Digest, Sha3_512};
+
+#[cfg(feature = "std")]
+use crate::DEFAULT_CONSTRUCTOR;
+use crate::{hash, sync::Mutex, Cuid2, CuidConstructor, BIG_LENGTH, COUNTER_INIT_MAX};
+
+/// Where a constructor gets its randomness.
+#[derive(Clone)]
+pub(crate) enum RngSource {
+    /// The thread-local `ThreadRng`, along with the thread-local counter and
+    /// fingerprint.
+    #[cfg(feature = "std")]
+    Thread,
+    /// A user-provided RNG, shared by all users of the constructor.
+    Custom(Arc<CustomRng>),
+}
+
+impl fmt::Debug for RngSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            #[cfg(feature = "std")]
+            Self::Thread => "Thread",
+            Self::Custom(_) => "Custom",
+        })
+    }
+}
+
+/// Where a constructor gets its counter and fingerprint, unless custom ones
+/// are configured.
+pub(crate) enum DefaultState<'a> {
+    /// The thread-local counter and fingerprint, accessed anew for each CUID.
+    #[cfg(feature = "std")]
+    ThreadLocal,
+    /// The thread-local counter and fingerprint, borrowed for a whole batch.
+    #[cfg(feature = "std")]
+    Thread(&'a mut crate::scope::ThreadState),
+    /// The process-wide counter and fingerprint.
+    #[cfg(feature = "std")]
+    Process(&'static crate::scope::ProcessState),
+    /// The counter and fingerprint derived from a custom RNG.
+    Seeded(Seeded<'a>),
+}
+
+impl DefaultState<'_> {
+    /// Hashes the count and then the fingerprint, using the default ones
+    /// unless custom ones are given.
+    ///
+    /// Both are handled together so that the thread-local state is accessed
+    /// only once per CUID. Custom counters and fingerprinters must be called
+    /// beforehand, since they could themselves create CUIDs.
+    pub(crate) fn hash_count_and_fingerprint(
+        &mut self,
+        hasher: &mut Sha3_512,
+        count: Option<u64>,
+        fingerprint: Option<&str>,
+    ) {
+        let mut hash = |count: u64, default_fingerprint: &str| {
+            hasher.update(to_base_36_in(count, &mut [0; MAX_BASE_36_LEN]));
+            hasher.update(fingerprint.unwrap_or(default_fingerprint));
+        };
+        match self {
+            Self::Seeded(seeded) => {
+                let count = count.unwrap_or_else(|| seeded.next_count());
+                hash(count, seeded.fingerprint())
+            }
+            #[cfg(feature = "std")]
+            Self::ThreadLocal => crate::scope::with_thread_state(|state| {
+                let count = count.unwrap_or_else(|| state.next_count());
+                hash(count, state.fingerprint())
+            }),
+            #[cfg(feature = "std")]
+            Self::Thread(state) => {
+                let count = count.unwrap_or_else(|| state.next_count());
+                hash(count, state.fingerprint())
+            }
+            #[cfg(feature = "std")]
+            Self::Process(state) => hash(
+                count.unwrap_or_else(|| state.next_count()),
+                state.fingerprint(),
+            ),
+        }
+    }
+}
+
+/// A user-provided RNG, along with the counter and fingerprint derived from it.
+pub(crate) struct CustomRng {
+    locked: Mutex<Locked>,
+    counter_init: u64,
+    fingerprint: String,
+}
+
+/// The parts of a [`CustomRng`] that are mutated during CUID creation.
+///
+/// The counter lives behind the lock alongside the RNG, rather than in an
+/// atomic, since not every `no_std` target has 64-bit atomics.
+struct Locked {
+    rng: Box<dyn RngCore + Send>,
+    counter: u64,
+}
+
+impl CustomRng {
+    /// Wraps the RNG, using it to initialize a counter and to create a
+    /// fingerprint.
+    ///
+    /// This mirrors the reference implementation's `init({ random })`.
+    pub(crate) fn new<R: RngCore + Send + 'static>(mut rng: R) -> Self {
+        let counter_init = rng.gen_range(0..COUNTER_INIT_MAX);
+        // Unlike the thread-local fingerprint, this is derived only from the
+        // (cryptographically secure) RNG, so that a seeded RNG yields
+        // reproducible CUIDs.
+        let fingerprint = hash(
+            [
+                rng.gen::<u128>().to_be_bytes(),
+                rng.gen::<u128>().to_be_bytes(),
+            ],
+            BIG_LENGTH.into(),
+        );
+        Self {
+            locked: Mutex::new(Locked {
+                rng: Box::new(rng),
+                counter: counter_init,
+            }),
+            counter_init,
+            fingerprint,
+        }
+    }
+
+    /// Calls `f` with exclusive access to the RNG and its derived state.
+    pub(crate) fn with<T>(&self, f: impl FnOnce(&mut (dyn RngCore + Send), Seeded<'_>) -> T) -> T {
+        let mut locked = self.locked.lock();
+        let Locked { rng, counter } = &mut *locked;
+        f(
+            &mut **rng,
+            Seeded {
+                counter,
+                counter_init: self.counter_init,
+                fingerprint: &self.fingerprint,
+            },
+        )
+    }
+}
+
+/// A counter and fingerprint derived from a constructor's RNG.
+///
+/// Since the state is shared by all users of the constructor, it is only
+/// available while holding the RNG's lock.
+pub(crate) struct Seeded<'a> {
+    counter: &'a mut u64,
+    counter_init: u64,
+    fingerprint: &'a str,
+}
+
+impl Seeded<'_> {
+    /// Retrieves and increments the counter value.
+    pub(crate) fn next_count(&mut self) -> u64 {
+        let count = *self.counter;
+        // if we hit u64::MAX, roll back to the initialization value
+        *self.counter = count.checked_add(1).unwrap_or(self.counter_init);
+        count
+    }
+
+    pub(crate) fn fingerprint(&self) -> &str {
+        self.fingerprint
+    }
+}
+
+/// Generates CUIDs using the default constructor's settings, with entropy from
+/// the sampling RNG.
+///
+/// This allows `rng.gen::<Cuid2>()`. Note that CUIDs are only as secure as the
+/// RNG used to create them.
+///
+/// ```
+/// use cuid2::Cuid2;
+/// use rand::Rng;
+///
+/// let id: Cuid2 = rand::thread_rng().gen();
+/// assert_eq!(24, id.len());
+/// ```
+#[cfg(feature = "std")]
+impl Distribution<Cuid2> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Cuid2 {
+        DEFAULT_CONSTRUCTOR.sample(rng)
+    }
+}
+
+/// Generates CUIDs using the constructor's settings, with entropy from the
+/// sampling RNG rather than the constructor's own RNG.
+///
+/// ```
+/// use cuid2::CuidConstructor;
+/// use rand::Rng;
+///
+/// let constructor = CuidConstructor::new().with_length(10);
+/// let id = rand::thread_rng().sample(&constructor);
+/// assert_eq!(10, id.len());
+/// ```
+impl Distribution<Cuid2> for CuidConstructor {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Cuid2 {
+        let mut buf = [0; crate::MAX_ID_LEN];
+        let id = match &self.rng {
+            #[cfg(feature = "std")]
+            RngSource::Thread => self.write_with(
+                &mut Sha3_512::new(),
+                rng,
+                &mut self.scoped_state(),
+                &mut buf,
+            ),
+            RngSource::Custom(custom) => custom.with(|_, seeded| {
+                self.write_with(
+                    &mut Sha3_512::new(),
+                    rng,
+                    &mut DefaultState::Seeded(seeded),
+                    &mut buf,
+                )
+            }),
+        };
+        Cuid2::from_string_unchecked(crate::expect_id(id).into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "std")]
+    use rand::rngs::OsRng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use super::*;
+    #[cfg(feature = "std")]
+    use crate::is_cuid2;
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn custom_rng() {
+        let constructor = CuidConstructor::new().with_rng(OsRng);
+        assert!(is_cuid2(constructor.create_id()));
+        assert!(is_cuid2(ChaCha20Rng::from_entropy().sample(&constructor)));
+        assert!(is_cuid2(ChaCha20Rng::from_entropy().gen::<Cuid2>()));
+    }
+
+    #[test]
+    fn seeded_state_is_reproducible() {
+        let seeded = |seed| {
+            let rng = CustomRng::new(ChaCha20Rng::seed_from_u64(seed));
+            rng.with(|_, mut seeded| (seeded.next_count(), String::from(seeded.fingerprint())))
+        };
+
+        assert_eq!(seeded(1), seeded(1));
+        assert_ne!(seeded(1), seeded(2));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn seeded_rng_and_fixed_clock_are_deterministic() {
+        let constructor = || {
+            CuidConstructor::new()
+                .with_rng(ChaCha20Rng::seed_from_u64(1))
+                .with_clock(crate::FixedClock::new(1_700_000_000_000))
+        };
+        let (first, second) = (constructor(), constructor());
+
+        assert_eq!(first.create_id(), second.create_id());
+        assert_eq!(first.create_id(), second.create_id());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn from_parts_matches_builder() {
+        let clock = crate::FixedClock::new(1_700_000_000_000);
+        let from_parts = CuidConstructor::from_parts(ChaCha20Rng::seed_from_u64(1), clock);
+        let built = CuidConstructor::new()
+            .with_rng(ChaCha20Rng::seed_from_u64(1))
+            .with_clock(clock);
+
+        assert_eq!(from_parts.create_id(), built.create_id());
+    }
+
+    #[test]
+    fn seeded_counter_increments() {
+        let rng = CustomRng::new(ChaCha20Rng::seed_from_u64(1));
+        let (first, second) = rng.with(|_, mut seeded| (seeded.next_count(), seeded.next_count()));
+        assert_eq!(first + 1, second);
+    }
+}
@@ -0,0 +1,71 @@
+//! Parallel CUID generation with rayon, enabled with the `rayon` feature.
+//!
+//! CUIDs are created on rayon's thread pool. Each worker thread uses its own
+//! counter and fingerprint (or the process-wide ones, with
+//! [`GeneratorScope::Process`](crate::GeneratorScope::Process)), exactly as
+//! if the CUIDs had been created on threads spawned by hand, so parallel
+//! generation is as collision resistant as sequential generation.
+
+use alloc::{string::String, vec::Vec};
+
+use ::rayon::prelude::*;
+
+use crate::{CuidConstructor, DEFAULT_CONSTRUCTOR};
+
+impl CuidConstructor {
+    /// Returns a parallel iterator of `n` new CUIDs.
+    ///
+    /// With a custom RNG, all worker threads share its lock, so prefer the
+    /// default, thread-local RNG for parallel generation.
+    ///
+    /// ```
+    /// use cuid2::CuidConstructor;
+    /// use rayon::prelude::*;
+    ///
+    /// let constructor = CuidConstructor::new().with_length(10);
+    /// let long = constructor.par_ids(100).filter(|id| id.len() == 10).count();
+    ///
+    /// assert_eq!(100, long);
+    /// ```
+    pub fn par_ids(&self, n: usize) -> impl IndexedParallelIterator<Item = String> + '_ {
+        (0..n).into_par_iter().map(|_| self.create_id())
+    }
+
+    /// Creates `n` new CUIDs in parallel.
+    ///
+    /// ```
+    /// use cuid2::CuidConstructor;
+    ///
+    /// let ids = CuidConstructor::new().par_create_ids(100);
+    /// assert_eq!(100, ids.len());
+    /// ```
+    pub fn par_create_ids(&self, n: usize) -> Vec<String> {
+        self.par_ids(n).collect()
+    }
+}
+
+/// Creates `n` new CUIDs in parallel, using the default settings.
+///
+/// ```
+/// let ids = cuid2::par_create_ids(100);
+/// assert!(ids.iter().all(cuid2::is_cuid2));
+/// ```
+pub fn par_create_ids(n: usize) -> Vec<String> {
+    DEFAULT_CONSTRUCTOR.par_create_ids(n)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::is_cuid2;
+
+    #[test]
+    fn parallel_ids_are_unique_and_valid() {
+        let ids = par_create_ids(10_000);
+        assert_eq!(10_000, ids.len());
+        assert_eq!(ids.len(), ids.iter().collect::<HashSet<_>>().len());
+        assert!(ids.iter().all(is_cuid2));
+    }
+}
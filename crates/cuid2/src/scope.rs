@@ -0,0 +1,403 @@
+//! Per-thread or process-wide CUID state
+
+use std::{
+    boxed::Box,
+    cell::RefCell,
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    process, ptr,
+    string::String,
+    sync::atomic::{AtomicPtr, AtomicU64, Ordering},
+};
+
+use cuid_util::{digest_to_base_36_in, DIGEST_BASE_36_BUF_LEN};
+use rand::{thread_rng, Rng};
+use sha3::{Digest, Sha3_512};
+
+use crate::{hash, BIG_LENGTH, COUNTER_INIT_MAX};
+
+/// Which threads share a counter and fingerprint.
+///
+/// By default, each thread has its own randomly initialized counter and its
+/// own fingerprint. That works well for a few long-lived threads, but with
+/// many short-lived threads (as in some async runtimes), every new thread
+/// pays to hash a new fingerprint, and each counter is only used briefly.
+/// With [`GeneratorScope::Process`], all threads instead share a single
+/// atomic counter and one fingerprint.
+///
+/// The scope only applies to constructors using the default, thread-local
+/// RNG. A constructor with a custom RNG always shares a single counter and
+/// fingerprint derived from that RNG.
+///
+/// In either scope, the counter and fingerprint are re-derived in a child
+/// process after a `fork()`. See [`reseed_after_fork()`].
+///
+/// ```
+/// use cuid2::{CuidConstructor, GeneratorScope};
+///
+/// let constructor = CuidConstructor::new().with_scope(GeneratorScope::Process);
+/// assert_eq!(24, constructor.create_id().len());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum GeneratorScope {
+    /// Each thread has its own counter and fingerprint.
+    #[default]
+    Thread,
+    /// All threads share one atomic counter and one fingerprint.
+    Process,
+}
+
+// =============================================================================
+// THREAD LOCALS
+// =============================================================================
+// Each thread generating CUIDs gets its own:
+// - 64-bit counter, initialized to some value between 0 and 476,782,366,
+//   inclusive
+// - fingerprint, a hash derived from the process-wide seed, the process ID,
+//   and the thread's ID and sequence number
+//
+// Both are re-derived if the process ID changes, since a forked child
+// otherwise inherits the parent's state.
+
+thread_local! {
+    static THREAD_STATE: RefCell<ThreadState> = RefCell::new(ThreadState::new());
+}
+
+/// A thread's counter and fingerprint.
+pub(crate) struct ThreadState {
+    /// The process ID when the state was created, used to detect forks.
+    pid: u32,
+    /// Value used to initialize the counter. After the counter hits u64::MAX, it
+    /// will roll back to this value.
+    counter_init: u64,
+    /// Use an individual counter per thread, starting at a pseudorandomly
+    /// initialized value.
+    ///
+    /// Range of initial values taken from reference implementation.
+    counter: u64,
+    /// Fingerprint! The original implementation is a hash of:
+    /// - stringified keys of the global object
+    /// - added entropy
+    ///
+    /// For us, we'll use
+    /// - random data, generated once per process
+    /// - the process ID
+    /// - the thread ID, and a sequence number unique to each thread's state
+    ///   (which also ensures our CUIDs will be different per thread)
+    ///
+    /// This is pretty non-language, non-system dependent, so it allows us to
+    /// compile to wasm and so on.
+    fingerprint: String,
+}
+
+impl ThreadState {
+    /// Derives a new state from the process-wide seed.
+    ///
+    /// Hashing the seed is much cheaper than generating fresh random data, so
+    /// starting a thread only costs a single hash. The result is as
+    /// unpredictable as the seed, and unique to the thread.
+    fn new() -> Self {
+        let process = ProcessState::get();
+        let sequence = process.threads.fetch_add(1, Ordering::Relaxed);
+        let digest = Sha3_512::new()
+            .chain_update(process.seed)
+            .chain_update(process.pid.to_be_bytes())
+            .chain_update(get_thread_id().to_be_bytes())
+            .chain_update(sequence.to_be_bytes())
+            .finalize();
+
+        // The fingerprint is the leading base36 digits of the digest, and so
+        // depends almost entirely on its leading bytes. The counter is taken
+        // from its trailing bytes.
+        let mut buf = [0; DIGEST_BASE_36_BUF_LEN];
+        let fingerprint = digest_to_base_36_in(&digest.into(), BIG_LENGTH.into(), &mut buf);
+        // Panic safety: a SHA3-512 digest is always 64 bytes
+        let tail = u64::from_be_bytes(digest[56..].try_into().unwrap());
+        let counter_init = tail % COUNTER_INIT_MAX;
+
+        Self {
+            pid: process.pid,
+            counter_init,
+            counter: counter_init,
+            fingerprint: fingerprint.to_owned(),
+        }
+    }
+
+    /// Retrieves and increments the counter value.
+    pub(crate) fn next_count(&mut self) -> u64 {
+        let count = self.counter;
+        self.counter = count
+            .checked_add(1)
+            // if we hit u64::MAX, roll back to the original thread-local
+            // initialization value
+            .unwrap_or(self.counter_init);
+        count
+    }
+
+    pub(crate) fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+}
+
+/// Calls `f` with this thread's state, first re-deriving it if the process
+/// has forked since it was created.
+///
+/// Everything a CUID needs from the thread's state is done in a single call,
+/// since each access to a thread local has a cost.
+pub(crate) fn with_thread_state<T>(f: impl FnOnce(&mut ThreadState) -> T) -> T {
+    THREAD_STATE.with(|cell| {
+        let mut state = cell.borrow_mut();
+        if state.pid != process::id() {
+            *state = ThreadState::new();
+        }
+        f(&mut state)
+    })
+}
+
+/// Retrieves the current thread's ID.
+fn get_thread_id() -> u64 {
+    // ThreadId doesn't implement debug or display, but it does implement Hash,
+    // so we can get the hash value to use in our fingerprint.
+    let mut hasher = DefaultHasher::new();
+    std::thread::current().id().hash(&mut hasher);
+    hasher.finish()
+}
+
+// =============================================================================
+// PROCESS-WIDE STATE
+// =============================================================================
+
+/// The current process-wide state, or null if it has not been created yet.
+///
+/// A replaced state is leaked rather than freed, since other threads may
+/// still be using it. This only happens once per fork (or per call to
+/// `reseed_after_fork()`).
+static PROCESS_STATE: AtomicPtr<ProcessState> = AtomicPtr::new(ptr::null_mut());
+
+/// The counter and fingerprint shared by all threads in
+/// [`GeneratorScope::Process`].
+pub(crate) struct ProcessState {
+    /// The process ID when the state was created, used to detect forks.
+    pid: u32,
+    counter_init: u64,
+    counter: AtomicU64,
+    fingerprint: String,
+    /// Random data from which each thread's state is derived.
+    seed: [u8; 32],
+    /// The number of thread states derived so far.
+    threads: AtomicU64,
+}
+
+impl ProcessState {
+    /// Returns the process-wide state, creating it on first use or if the
+    /// process has forked since it was created.
+    pub(crate) fn get() -> &'static Self {
+        let current = PROCESS_STATE.load(Ordering::Acquire);
+        // SAFETY: the pointer is either null or was created by
+        // `Box::into_raw()`, and is never freed.
+        match unsafe { current.as_ref() } {
+            Some(state) if state.pid == process::id() => state,
+            _ => Self::replace(current),
+        }
+    }
+
+    /// Replaces the process-wide state with a new one, unless another
+    /// thread has already replaced `current`.
+    fn replace(current: *mut Self) -> &'static Self {
+        let new = Box::into_raw(Box::new(Self::new()));
+        match PROCESS_STATE.compare_exchange(current, new, Ordering::AcqRel, Ordering::Acquire) {
+            // SAFETY: we just created the pointer from a box, and it is never
+            // freed.
+            Ok(_) => unsafe { &*new },
+            Err(winner) => {
+                // SAFETY: we created the pointer from a box above and failed
+                // to publish it, so no one else can have seen it.
+                drop(unsafe { Box::from_raw(new) });
+                // SAFETY: the winning pointer was published by another thread
+                // from a box, is never freed, and is never null since it
+                // replaced `current`.
+                unsafe { &*winner }
+            }
+        }
+    }
+
+    fn new() -> Self {
+        let pid = process::id();
+        let mut rng = thread_rng();
+        let counter_init = rng.gen_range(0..COUNTER_INIT_MAX);
+        Self {
+            pid,
+            counter_init,
+            counter: AtomicU64::new(counter_init),
+            fingerprint: hash(
+                [
+                    rng.gen::<u128>().to_be_bytes(),
+                    rng.gen::<u128>().to_be_bytes(),
+                    u128::from(pid).to_be_bytes(),
+                ],
+                BIG_LENGTH.into(),
+            ),
+            seed: rng.gen(),
+            threads: AtomicU64::new(0),
+        }
+    }
+
+    /// Retrieves and increments the counter value.
+    pub(crate) fn next_count(&self) -> u64 {
+        let mut counter = self.counter.load(Ordering::Relaxed);
+        loop {
+            // if we hit u64::MAX, roll back to the initialization value
+            let next = counter.checked_add(1).unwrap_or(self.counter_init);
+            match self.counter.compare_exchange_weak(
+                counter,
+                next,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(previous) => return previous,
+                Err(current) => counter = current,
+            }
+        }
+    }
+
+    pub(crate) fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+}
+
+/// Re-derives the counter and fingerprint for the current thread and for
+/// [`GeneratorScope::Process`].
+///
+/// A process created with `fork()` inherits its parent's memory, including
+/// the state used to generate CUIDs. This is detected automatically by
+/// comparing process IDs before each CUID is created, so calling this is
+/// not required. It may be called in a child process (e.g. in a prefork
+/// server's post-fork hook) to reseed eagerly, rather than when the first
+/// CUID is created.
+///
+/// Only the calling thread's state needs reseeding, since it is the only
+/// thread in a forked child. Constructors with a custom RNG are unaffected:
+/// their RNG, and so the counter and fingerprint derived from it, must be
+/// reseeded by creating a new constructor.
+///
+/// ```
+/// cuid2::reseed_after_fork();
+/// assert_eq!(24, cuid2::create_id().len());
+/// ```
+pub fn reseed_after_fork() {
+    // Thread states are derived from the process-wide state, so it must be
+    // replaced first.
+    ProcessState::replace(PROCESS_STATE.load(Ordering::Acquire));
+    THREAD_STATE.with(|cell| cell.replace(ThreadState::new()));
+}
+
+#[cfg(test)]
+mod test {
+    use std::{borrow::ToOwned, collections::HashSet, sync::Mutex, thread};
+
+    use super::*;
+
+    /// Serializes tests that replace or inspect the process-wide state.
+    static PROCESS_STATE_LOCK: Mutex<()> = Mutex::new(());
+
+    /// The current thread's fingerprint and the process-wide fingerprint.
+    fn fingerprints() -> (String, String) {
+        (
+            with_thread_state(|state| state.fingerprint().to_owned()),
+            ProcessState::get().fingerprint().to_owned(),
+        )
+    }
+
+    #[test]
+    fn state_is_shared_by_threads() {
+        let _lock = PROCESS_STATE_LOCK.lock().unwrap();
+        let here = ProcessState::get();
+        let there = thread::spawn(ProcessState::get).join().unwrap();
+        assert!(ptr::eq(here, there));
+
+        let first = here.next_count();
+        assert!(there.next_count() > first);
+    }
+
+    #[test]
+    fn thread_states_are_unique() {
+        let states: HashSet<_> = (0..8)
+            .map(|_| {
+                thread::spawn(|| {
+                    with_thread_state(|state| {
+                        assert!(state.counter_init < COUNTER_INIT_MAX);
+                        assert_eq!(usize::from(BIG_LENGTH), state.fingerprint.len());
+                        (state.counter_init, state.fingerprint.clone())
+                    })
+                })
+            })
+            .map(|handle| handle.join().unwrap())
+            .collect();
+        assert_eq!(8, states.len());
+    }
+
+    #[test]
+    fn reseed_after_fork_changes_state() {
+        let _lock = PROCESS_STATE_LOCK.lock().unwrap();
+        let before = fingerprints();
+        reseed_after_fork();
+        let after = fingerprints();
+
+        assert_ne!(before.0, after.0);
+        assert_ne!(before.1, after.1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn forked_child_is_reseeded() {
+        use std::{fs::File, io::Read, os::unix::io::FromRawFd};
+
+        let _lock = PROCESS_STATE_LOCK.lock().unwrap();
+        let parent = fingerprints();
+
+        let mut fds = [0; 2];
+        // SAFETY: fds has room for the two file descriptors
+        assert_eq!(0, unsafe { libc::pipe(fds.as_mut_ptr()) });
+        let [read_fd, write_fd] = fds;
+
+        // SAFETY: the child only generates a CUID, writes to the pipe, and
+        // exits without running any destructors or test harness code.
+        match unsafe { libc::fork() } {
+            -1 => panic!("fork failed"),
+            0 => {
+                let child = std::panic::catch_unwind(|| {
+                    // Reseeding happens automatically when generating a CUID
+                    crate::create_id();
+                    let (thread, process) = fingerprints();
+                    std::format!("{thread} {process}")
+                })
+                .unwrap_or_default();
+                // SAFETY: we own the write end of the pipe, and `_exit()`
+                // never returns.
+                unsafe {
+                    libc::write(write_fd, child.as_ptr().cast(), child.len());
+                    libc::_exit(0);
+                }
+            }
+            child_pid => {
+                // SAFETY: we own the write end of the pipe, and the read end
+                // is wrapped in exactly one File.
+                let mut pipe = unsafe {
+                    libc::close(write_fd);
+                    File::from_raw_fd(read_fd)
+                };
+                let mut child = String::new();
+                pipe.read_to_string(&mut child).unwrap();
+                // SAFETY: child_pid is our own child process
+                unsafe { libc::waitpid(child_pid, ptr::null_mut(), 0) };
+
+                let (thread, process) = child.split_once(' ').expect("child failed");
+                assert_ne!(parent.0, thread);
+                assert_ne!(parent.1, process);
+
+                // The parent's own state is unchanged
+                assert_eq!(parent, fingerprints());
+            }
+        }
+    }
+}
@@ -0,0 +1,387 @@
+//! Serde support for [`Cuid2`], enabled with the `serde` feature.
+//!
+//! By default, a [`Cuid2`] is serialized as a string in every format, and
+//! deserialization fails for any string that is not a valid CUID:
+//!
+//! ```
+//! # use cuid2::Cuid2;
+//! let id: Cuid2 = "tz4a98xxat96iws9zmbrgj3a".parse().unwrap();
+//! let json = serde_json::to_string(&id).unwrap();
+//! assert_eq!(r#""tz4a98xxat96iws9zmbrgj3a""#, json);
+//!
+//! assert!(serde_json::from_str::<Cuid2>(r#""Not-A-Cuid""#).is_err());
+//! ```
+//!
+//! For non-human-readable formats like bincode or postcard, the [`compact`]
+//! module may be used to opt into a denser binary encoding.
+
+use alloc::string::String;
+use core::fmt;
+
+use ::serde::{
+    de::{self, Unexpected, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{is_cuid2, Cuid2, Cuid2Str};
+
+/// Describes a valid CUID for deserialization error messages.
+const EXPECTING: &str = "a CUID2: a lowercase ASCII letter followed by 1 to 75 \
+                         lowercase ASCII letters or digits";
+
+impl Serialize for Cuid2 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Cuid2 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(Cuid2Visitor)
+    }
+}
+
+struct Cuid2Visitor;
+
+impl<'de> Visitor<'de> for Cuid2Visitor {
+    type Value = Cuid2;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(EXPECTING)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        v.parse()
+            .map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        if is_cuid2(&v) {
+            Ok(Cuid2::from_string_unchecked(v))
+        } else {
+            Err(E::invalid_value(Unexpected::Str(&v), &self))
+        }
+    }
+}
+
+impl Serialize for Cuid2Str {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// Deserializes a CUID borrowed from the input, which fails if the format
+/// cannot borrow strings (for example, JSON strings containing escapes).
+impl<'de: 'a, 'a> Deserialize<'de> for &'a Cuid2Str {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(Cuid2StrVisitor)
+    }
+}
+
+struct Cuid2StrVisitor;
+
+impl<'de> Visitor<'de> for Cuid2StrVisitor {
+    type Value = &'de Cuid2Str;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a borrowed string containing {EXPECTING}")
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
+        Cuid2Str::new(v).map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+    }
+}
+
+/// Compact binary serialization for non-human-readable formats.
+///
+/// Use with `#[serde(with = "cuid2::serde::compact")]`. For human-readable
+/// formats the ID is still serialized as a string. Otherwise, each character
+/// is packed into six bits, so that a 24-character CUID takes up 18 bytes
+/// instead of 24.
+///
+/// ```
+/// use cuid2::Cuid2;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct User {
+///     #[serde(with = "cuid2::serde::compact")]
+///     id: Cuid2,
+/// }
+///
+/// let user = User { id: cuid2::create_cuid2() };
+/// let bytes = bincode::serialize(&user).unwrap();
+///
+/// // 8 byte length prefix plus 18 bytes of packed data
+/// assert_eq!(26, bytes.len());
+/// assert_eq!(user.id, bincode::deserialize::<User>(&bytes).unwrap().id);
+/// ```
+pub mod compact {
+    use super::*;
+
+    /// Bits used to store each character.
+    const BITS_PER_CHAR: usize = 6;
+    /// Mask for a single six-bit slot.
+    const SLOT_MASK: u8 = 0b11_1111;
+    /// Slot value that never represents a character, used to mark padding.
+    ///
+    /// Characters are stored as their base36 value plus one, so that padding
+    /// is all zero bits.
+    const PADDING: u8 = 0;
+    /// Enough bytes to hold the longest valid CUID.
+    const MAX_PACKED_LEN: usize =
+        (crate::CuidConstructor::MAX_LENGTH as usize * BITS_PER_CHAR).div_ceil(8);
+
+    /// Serializes a [`Cuid2`] as packed bytes for non-human-readable formats.
+    pub fn serialize<S: Serializer>(id: &Cuid2, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return id.serialize(serializer);
+        }
+        let mut buf = [0; MAX_PACKED_LEN];
+        let len = pack(id.as_str(), &mut buf);
+        serializer.serialize_bytes(&buf[..len])
+    }
+
+    /// Deserializes a [`Cuid2`] written by [`serialize`].
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Cuid2, D::Error> {
+        if deserializer.is_human_readable() {
+            return Cuid2::deserialize(deserializer);
+        }
+        deserializer.deserialize_bytes(PackedVisitor)
+    }
+
+    struct PackedVisitor;
+
+    impl<'de> Visitor<'de> for PackedVisitor {
+        type Value = Cuid2;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(
+                formatter,
+                "{EXPECTING}, packed into at most {MAX_PACKED_LEN} bytes"
+            )
+        }
+
+        fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+            unpack(v)
+                .filter(|id| is_cuid2(id))
+                .map(Cuid2::from_string_unchecked)
+                .ok_or_else(|| E::invalid_value(Unexpected::Bytes(v), &self))
+        }
+
+        /// Accepts the packed bytes as a sequence, as formats without a
+        /// native byte string type may represent them.
+        fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut buf = [0; MAX_PACKED_LEN];
+            let mut len = 0;
+            while let Some(byte) = seq.next_element()? {
+                if len == MAX_PACKED_LEN {
+                    return Err(de::Error::invalid_length(len + 1, &self));
+                }
+                buf[len] = byte;
+                len += 1;
+            }
+            self.visit_bytes(&buf[..len])
+        }
+    }
+
+    /// Packs each character of a valid CUID into six bits, returning the
+    /// number of bytes written.
+    ///
+    /// Unused trailing bits are cleared, and since no character is stored as
+    /// zero, a trailing six-bit slot of padding can be distinguished from a
+    /// character when unpacking.
+    fn pack(id: &str, buf: &mut [u8; MAX_PACKED_LEN]) -> usize {
+        let mut acc: u16 = 0;
+        let mut acc_bits = 0;
+        let mut len = 0;
+
+        for ch in id.bytes() {
+            let value = match ch {
+                b'0'..=b'9' => ch - b'0' + 1,
+                b'a'..=b'z' => ch - b'a' + 11,
+                // Panic safety: a Cuid2 is validated on construction.
+                _ => unreachable!("Cuid2 contains only lowercase letters and digits"),
+            };
+            acc = (acc << BITS_PER_CHAR) | u16::from(value);
+            acc_bits += BITS_PER_CHAR;
+            if acc_bits >= 8 {
+                acc_bits -= 8;
+                buf[len] = (acc >> acc_bits) as u8;
+                len += 1;
+            }
+        }
+
+        if acc_bits > 0 {
+            let pad_bits = 8 - acc_bits;
+            buf[len] = (acc << pad_bits) as u8;
+            len += 1;
+        }
+
+        len
+    }
+
+    /// Reverses [`pack`], returning `None` if the bytes contain anything but
+    /// characters and trailing padding, or if they are not exactly as `pack`
+    /// would have written them.
+    fn unpack(bytes: &[u8]) -> Option<String> {
+        if bytes.len() > MAX_PACKED_LEN {
+            return None;
+        }
+
+        let slots = bytes.len() * 8 / BITS_PER_CHAR;
+        let mut result = String::with_capacity(slots);
+        let mut acc: u16 = 0;
+        let mut acc_bits = 0;
+        let mut iter = bytes.iter();
+
+        for slot in 0..slots {
+            if acc_bits < BITS_PER_CHAR {
+                acc = (acc << 8) | u16::from(*iter.next()?);
+                acc_bits += 8;
+            }
+            acc_bits -= BITS_PER_CHAR;
+            let value = ((acc >> acc_bits) as u8) & SLOT_MASK;
+            match value {
+                1..=36 => result.push(char::from_digit(u32::from(value) - 1, 36)?),
+                PADDING if slot == slots - 1 => {}
+                _ => return None,
+            }
+        }
+
+        // Any bits left over in the final byte are padding, and must be clear
+        let leftover = acc & ((1 << acc_bits) - 1);
+        // A padding slot is only written when needed to fill the final byte
+        let canonical_len = (result.len() * BITS_PER_CHAR).div_ceil(8);
+        (leftover == 0 && canonical_len == bytes.len()).then_some(result)
+    }
+
+    #[cfg(test)]
+    mod test {
+        use proptest::prelude::*;
+
+        use super::*;
+
+        proptest! {
+            #[test]
+            fn pack_roundtrip(id in "[a-z][a-z0-9]{1,75}") {
+                let mut buf = [0; MAX_PACKED_LEN];
+                let len = pack(&id, &mut buf);
+                assert_eq!((id.len() * BITS_PER_CHAR).div_ceil(8), len);
+                assert_eq!(Some(id), unpack(&buf[..len]));
+            }
+        }
+
+        #[test]
+        #[allow(clippy::unusual_byte_groupings)] // grouped by six-bit slot
+        fn unpack_rejects_bad_slots() {
+            // 0xff is a slot of 63 followed by two bits: not a valid character
+            assert_eq!(None, unpack(&[0xff, 0xff, 0xff]));
+            // Padding before the final slot
+            assert_eq!(None, unpack(&[0b000000_00, 0b0001_0000, 0b01_000001]));
+            assert_eq!(None, unpack(&[0; MAX_PACKED_LEN + 1]));
+        }
+
+        #[test]
+        #[allow(clippy::unusual_byte_groupings)] // grouped by six-bit slot
+        fn unpack_rejects_set_padding_bits() {
+            // "a": one slot of 11, then two padding bits
+            assert_eq!(Some("a".into()), unpack(&[0b001011_00]));
+            assert_eq!(None, unpack(&[0b001011_01]));
+            assert_eq!(None, unpack(&[0b001011_10]));
+
+            // "ab": two slots, then four padding bits
+            assert_eq!(Some("ab".into()), unpack(&[0b001011_00, 0b1100_0000]));
+            assert_eq!(None, unpack(&[0b001011_00, 0b1100_1000]));
+
+            // "abc": three slots, then a whole padding slot
+            let abc = [0b001011_00, 0b1100_0011, 0b01_000000];
+            assert_eq!(Some("abc".into()), unpack(&abc));
+            assert_eq!(None, unpack(&[abc[0], abc[1], abc[2] | SLOT_MASK]));
+            // An extra byte of padding is not canonical
+            assert_eq!(None, unpack(&[0b001011_00, 0]));
+        }
+
+        #[test]
+        fn visit_seq_matches_visit_bytes() {
+            use ::serde::de::value::{Error, SeqDeserializer};
+
+            let id: Cuid2 = "tz4a98xxat96iws9zmbrgj3a".parse().unwrap();
+            let mut buf = [0; MAX_PACKED_LEN];
+            let len = pack(id.as_str(), &mut buf);
+
+            let seq = SeqDeserializer::<_, Error>::new(buf[..len].iter().copied());
+            assert_eq!(Ok(id), PackedVisitor.visit_seq(seq));
+
+            let too_long = SeqDeserializer::<_, Error>::new(0..=MAX_PACKED_LEN as u8);
+            assert!(PackedVisitor.visit_seq(too_long).is_err());
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use ::serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Record {
+        id: Cuid2,
+        #[serde(with = "compact")]
+        compact_id: Cuid2,
+    }
+
+    fn record() -> Record {
+        Record {
+            id: crate::create_cuid2(),
+            compact_id: crate::CuidConstructor::new().with_length(32).create_cuid2(),
+        }
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        let record = record();
+        let json = serde_json::to_string(&record).unwrap();
+        assert_eq!(
+            format!(
+                r#"{{"id":"{}","compact_id":"{}"}}"#,
+                record.id, record.compact_id
+            ),
+            json
+        );
+        assert_eq!(record, serde_json::from_str(&json).unwrap());
+    }
+
+    #[test]
+    fn bincode_roundtrip() {
+        let record = record();
+        let bytes = bincode::serialize(&record).unwrap();
+        // Two 8-byte length prefixes, a 24-char string and 24 bytes of packed data
+        assert_eq!(8 + 24 + 8 + 24, bytes.len());
+        assert_eq!(record, bincode::deserialize(&bytes).unwrap());
+    }
+
+    #[test]
+    fn borrowed_roundtrip() {
+        let id = crate::create_cuid2();
+        let json = serde_json::to_string(id.as_cuid2_str()).unwrap();
+        let borrowed: &Cuid2Str = serde_json::from_str(&json).unwrap();
+        assert_eq!(id, borrowed);
+
+        let bytes = bincode::serialize(&id).unwrap();
+        let borrowed: &Cuid2Str = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(id, borrowed);
+
+        assert!(serde_json::from_str::<&Cuid2Str>(r#""Abcd""#).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        let err = serde_json::from_str::<Cuid2>(r#""ab#cd""#).unwrap_err();
+        assert!(err.to_string().contains("expected a CUID2"), "{err}");
+
+        let bytes = bincode::serialize("Abcd").unwrap();
+        assert!(bincode::deserialize::<Cuid2>(&bytes).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+//! sqlx support for [`Cuid2`], enabled with the `sqlx` feature.
+//!
+//! A [`Cuid2`] is stored as text, and can be used with any database whose
+//! driver supports `String`, including SQLite and Postgres. Decoding a value
+//! that is not a valid CUID fails with a [`ParseCuid2Error`](crate::ParseCuid2Error).
+
+use ::sqlx::{encode::IsNull, error::BoxDynError, Database, Decode, Encode, Type};
+
+use crate::Cuid2;
+
+impl<DB: Database> Type<DB> for Cuid2
+where
+    String: Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as Type<DB>>::compatible(ty)
+    }
+}
+
+impl<'q, DB: Database> Encode<'q, DB> for Cuid2
+where
+    String: Encode<'q, DB>,
+{
+    fn encode_by_ref(
+        &self,
+        buf: &mut <DB as Database>::ArgumentBuffer<'q>,
+    ) -> Result<IsNull, BoxDynError> {
+        self.as_string().encode_by_ref(buf)
+    }
+
+    fn size_hint(&self) -> usize {
+        self.as_string().size_hint()
+    }
+}
+
+impl<'r, DB: Database> Decode<'r, DB> for Cuid2
+where
+    &'r str: Decode<'r, DB>,
+{
+    fn decode(value: <DB as Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(<&str as Decode<DB>>::decode(value)?.parse()?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::sqlx::{Connection, Postgres, SqliteConnection};
+
+    use super::*;
+
+    async fn connect() -> SqliteConnection {
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        ::sqlx::query("CREATE TABLE ids (id TEXT NOT NULL)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        conn
+    }
+
+    #[tokio::test]
+    async fn sqlite_roundtrip() {
+        let mut conn = connect().await;
+        let id = crate::create_cuid2();
+
+        ::sqlx::query("INSERT INTO ids (id) VALUES (?)")
+            .bind(&id)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        let fetched: Cuid2 = ::sqlx::query_scalar("SELECT id FROM ids WHERE id = ?")
+            .bind(&id)
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(id, fetched);
+    }
+
+    #[tokio::test]
+    async fn sqlite_rejects_invalid() {
+        let mut conn = connect().await;
+
+        ::sqlx::query("INSERT INTO ids (id) VALUES ('Not-A-Cuid')")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        let res = ::sqlx::query_scalar::<_, Cuid2>("SELECT id FROM ids")
+            .fetch_one(&mut conn)
+            .await;
+        assert!(
+            matches!(res, Err(::sqlx::Error::ColumnDecode { .. })),
+            "{res:?}"
+        );
+    }
+
+    #[test]
+    fn postgres_text() {
+        fn assert_sql_type<T>()
+        where
+            T: Type<Postgres> + for<'q> Encode<'q, Postgres> + for<'r> Decode<'r, Postgres>,
+        {
+        }
+
+        assert_sql_type::<Cuid2>();
+        assert_eq!(
+            <String as Type<Postgres>>::type_info(),
+            <Cuid2 as Type<Postgres>>::type_info()
+        );
+    }
+}
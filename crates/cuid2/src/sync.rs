@@ -0,0 +1,98 @@
+//! A mutex that works with or without `std`
+//!
+//! With `std`, this wraps `std::sync::Mutex`. Without it, there is no way to
+//! park a thread, so a minimal spin lock is used instead. The lock is only
+//! ever held for the duration of a single CUID's generation.
+
+use core::ops::DerefMut;
+
+/// A mutex that ignores poisoning.
+#[cfg(feature = "std")]
+pub(crate) struct Mutex<T>(std::sync::Mutex<T>);
+
+#[cfg(feature = "std")]
+impl<T> Mutex<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Self(std::sync::Mutex::new(value))
+    }
+
+    /// Acquires the lock.
+    pub(crate) fn lock(&self) -> impl DerefMut<Target = T> + '_ {
+        // A panic while holding the lock cannot leave the protected state
+        // invalid, so we can ignore poisoning.
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// A spin lock, for use without `std`.
+#[cfg(not(feature = "std"))]
+pub(crate) struct Mutex<T> {
+    locked: core::sync::atomic::AtomicBool,
+    value: core::cell::UnsafeCell<T>,
+}
+
+// SAFETY: access to the value is serialized by the lock, so sharing the mutex
+// only requires that the value can be sent between threads.
+#[cfg(not(feature = "std"))]
+unsafe impl<T: Send> Sync for Mutex<T> {}
+
+#[cfg(not(feature = "std"))]
+impl<T> Mutex<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Self {
+            locked: core::sync::atomic::AtomicBool::new(false),
+            value: core::cell::UnsafeCell::new(value),
+        }
+    }
+
+    /// Acquires the lock, spinning until it is available.
+    pub(crate) fn lock(&self) -> impl DerefMut<Target = T> + '_ {
+        use core::sync::atomic::Ordering;
+
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            while self.locked.load(Ordering::Relaxed) {
+                core::hint::spin_loop();
+            }
+        }
+        SpinGuard { mutex: self }
+    }
+}
+
+/// Releases a spin lock when dropped.
+#[cfg(not(feature = "std"))]
+struct SpinGuard<'a, T> {
+    mutex: &'a Mutex<T>,
+}
+
+#[cfg(not(feature = "std"))]
+impl<T> core::ops::Deref for SpinGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: holding the guard means we hold the lock
+        unsafe { &*self.mutex.value.get() }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<T> DerefMut for SpinGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: holding the guard means we hold the lock
+        unsafe { &mut *self.mutex.value.get() }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<T> Drop for SpinGuard<'_, T> {
+    fn drop(&mut self) {
+        self.mutex
+            .locked
+            .store(false, core::sync::atomic::Ordering::Release);
+    }
+}
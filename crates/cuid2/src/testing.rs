@@ -0,0 +1,363 @@
+//! Statistical checks of CUID quality, enabled with the `testing` feature.
+//!
+//! These are the checks used by the reference implementation's test suite,
+//! run against any [`CuidConstructor`], so that a configured constructor
+//! (e.g. with a custom length, fingerprinter, or RNG) can be checked before
+//! it is deployed. Each check returns a report rather than panicking, so the
+//! caller decides what counts as a failure, or an [`Error`] if the check is
+//! misconfigured or the constructor fails to create a CUID.
+//!
+//! ```
+//! use cuid2::{testing, CuidConstructor};
+//!
+//! let constructor = CuidConstructor::new().with_length(16);
+//!
+//! let histogram = testing::histogram(&constructor, 20_000, 20)?;
+//! assert!(histogram.chi_square().is_uniform(0.001));
+//!
+//! let collisions = testing::collisions(&constructor, 20_000, 4)?;
+//! assert!(collisions.is_collision_free());
+//! # Ok::<(), cuid2::testing::Error>(())
+//! ```
+//!
+//! Like any statistical test, a uniformity check will occasionally fail for a
+//! perfectly good constructor, at a rate given by the chosen significance.
+
+use std::{
+    collections::HashSet, f64::consts::SQRT_2, fmt, panic, string::String, thread, vec, vec::Vec,
+};
+
+use crate::CuidConstructor;
+
+/// The error returned when a check cannot be run.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A histogram was requested with fewer than two buckets.
+    TooFewBuckets {
+        /// The requested number of buckets.
+        buckets: usize,
+    },
+    /// A collision check was requested with zero threads.
+    NoThreads,
+    /// The constructor failed to create a CUID.
+    Create(crate::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooFewBuckets { buckets } => write!(
+                f,
+                "Invalid number of buckets {buckets}: a histogram needs at least two"
+            ),
+            Self::NoThreads => f.write_str("At least one thread is required"),
+            Self::Create(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<crate::Error> for Error {
+    fn from(err: crate::Error) -> Self {
+        Self::Create(err)
+    }
+}
+
+/// The distribution of CUIDs across a number of buckets.
+///
+/// Created by [`histogram()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Histogram {
+    buckets: Vec<u64>,
+    samples: usize,
+}
+
+impl Histogram {
+    /// Returns the number of CUIDs in each bucket.
+    pub fn buckets(&self) -> &[u64] {
+        &self.buckets
+    }
+
+    /// Returns the total number of CUIDs.
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Returns the number of CUIDs expected in each bucket, if they are
+    /// uniformly distributed.
+    pub fn expected(&self) -> f64 {
+        self.samples as f64 / self.buckets.len() as f64
+    }
+
+    /// Returns the largest difference between any bucket's size and the
+    /// expected size, relative to the expected size.
+    ///
+    /// The reference implementation requires this to be less than 0.05 for
+    /// 20 buckets of 50,000 CUIDs each.
+    pub fn max_deviation(&self) -> f64 {
+        let expected = self.expected();
+        self.buckets
+            .iter()
+            .map(|&size| (size as f64 - expected).abs() / expected)
+            .fold(0.0, f64::max)
+    }
+
+    /// Runs Pearson's chi-square test of the buckets against a uniform
+    /// distribution.
+    pub fn chi_square(&self) -> ChiSquare {
+        let expected = self.expected();
+        let statistic = self
+            .buckets
+            .iter()
+            .map(|&size| (size as f64 - expected).powi(2) / expected)
+            .sum();
+        let degrees_of_freedom = self.buckets.len() - 1;
+        ChiSquare {
+            statistic,
+            degrees_of_freedom,
+            p_value: chi_square_upper_tail(statistic, degrees_of_freedom),
+        }
+    }
+}
+
+/// The result of a chi-square test against a uniform distribution.
+///
+/// Created by [`Histogram::chi_square()`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct ChiSquare {
+    /// The chi-square statistic.
+    pub statistic: f64,
+    /// One less than the number of buckets.
+    pub degrees_of_freedom: usize,
+    /// The approximate probability of a statistic at least this large if the
+    /// CUIDs were uniformly distributed.
+    pub p_value: f64,
+}
+
+impl ChiSquare {
+    /// Returns whether the distribution is consistent with a uniform one at
+    /// the given significance level, such as 0.01 or 0.001.
+    pub fn is_uniform(&self, significance: f64) -> bool {
+        self.p_value >= significance
+    }
+}
+
+/// The result of checking a number of CUIDs for collisions.
+///
+/// Created by [`collisions()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CollisionReport {
+    /// The number of CUIDs created.
+    pub samples: usize,
+    /// Each CUID that was created more than once, in no particular order.
+    pub duplicates: Vec<String>,
+}
+
+impl CollisionReport {
+    /// Returns whether every CUID was unique.
+    pub fn is_collision_free(&self) -> bool {
+        self.duplicates.is_empty()
+    }
+}
+
+/// Creates `samples` CUIDs and sorts them into `buckets` buckets.
+///
+/// Like the reference implementation's histogram, each CUID except for its
+/// first character is read as a base36 number, and its bucket is that number
+/// modulo the number of buckets.
+///
+/// Returns an error if there are fewer than two buckets, or if the
+/// constructor fails to create a CUID.
+pub fn histogram(
+    constructor: &CuidConstructor,
+    samples: usize,
+    buckets: usize,
+) -> Result<Histogram, Error> {
+    if buckets < 2 {
+        return Err(Error::TooFewBuckets { buckets });
+    }
+    let modulus = buckets as u128;
+
+    let mut histogram = Histogram {
+        buckets: vec![0; buckets],
+        samples,
+    };
+    for _ in 0..samples {
+        let id = constructor.try_create_id()?;
+        let bucket = id[1..].chars().fold(0, |acc, ch| {
+            // Panic safety: CUIDs contain only base36 digits
+            let digit = ch.to_digit(36).expect("CUIDs are base36");
+            (acc * 36 + u128::from(digit)) % modulus
+        });
+        histogram.buckets[bucket as usize] += 1;
+    }
+    Ok(histogram)
+}
+
+/// Creates `samples` CUIDs, split across `threads` threads, and reports any
+/// that were created more than once.
+///
+/// Creating the CUIDs concurrently checks that threads using the same
+/// constructor do not produce the same CUIDs.
+///
+/// Returns an error if `threads` is zero, or if the constructor fails to
+/// create a CUID.
+pub fn collisions(
+    constructor: &CuidConstructor,
+    samples: usize,
+    threads: usize,
+) -> Result<CollisionReport, Error> {
+    if threads == 0 {
+        return Err(Error::NoThreads);
+    }
+
+    let batches: Vec<Result<Vec<String>, crate::Error>> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|thread| {
+                // Spread the remainder over the first few threads
+                let n = samples / threads + usize::from(thread < samples % threads);
+                scope.spawn(move || (0..n).map(|_| constructor.try_create_id()).collect())
+            })
+            .collect();
+        handles
+            .into_iter()
+            // A worker only panics if a custom clock, counter, or
+            // fingerprinter does, so propagate it as is
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|err| panic::resume_unwind(err))
+            })
+            .collect()
+    });
+
+    let mut seen = HashSet::with_capacity(samples);
+    let mut duplicates = HashSet::new();
+    for batch in batches {
+        for id in batch? {
+            if let Some(id) = seen.replace(id) {
+                duplicates.insert(id);
+            }
+        }
+    }
+
+    Ok(CollisionReport {
+        samples,
+        duplicates: duplicates.into_iter().collect(),
+    })
+}
+
+/// Approximates the probability that a chi-square random variable with the
+/// given degrees of freedom is at least `statistic`.
+///
+/// Uses the Wilson-Hilferty transformation to a standard normal variable,
+/// which is accurate to about three decimal places for the degrees of freedom
+/// used with CUID histograms.
+fn chi_square_upper_tail(statistic: f64, degrees_of_freedom: usize) -> f64 {
+    let k = degrees_of_freedom as f64;
+    let variance = 2.0 / (9.0 * k);
+    let z = ((statistic / k).cbrt() - (1.0 - variance)) / variance.sqrt();
+    0.5 * erfc(z / SQRT_2)
+}
+
+/// Approximates the complementary error function, with an absolute error of
+/// less than 1.5e-7.
+///
+/// See Abramowitz and Stegun, formula 7.1.26.
+fn erfc(x: f64) -> f64 {
+    if x < 0.0 {
+        return 2.0 - erfc(-x);
+    }
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let polynomial = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    polynomial * (-x * x).exp()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn chi_square_p_values() {
+        // Critical values from standard chi-square tables
+        for (statistic, degrees_of_freedom, p_value) in
+            [(30.144, 19, 0.05), (43.820, 19, 0.001), (66.619, 35, 0.001)]
+        {
+            let approximation = chi_square_upper_tail(statistic, degrees_of_freedom);
+            assert!(
+                (approximation - p_value).abs() < p_value * 0.1,
+                "{statistic}, {degrees_of_freedom}: {approximation}"
+            );
+        }
+    }
+
+    #[test]
+    fn reports_uniform_distribution() {
+        let histogram = histogram(&CuidConstructor::new().with_length(8), 20_000, 20).unwrap();
+        assert_eq!(20_000, histogram.buckets().iter().sum::<u64>());
+        assert!(histogram.max_deviation() < 0.15, "{histogram:?}");
+        assert!(histogram.chi_square().p_value > 0.0);
+    }
+
+    #[test]
+    fn reports_non_uniform_distribution() {
+        let histogram = Histogram {
+            buckets: vec![150, 50, 100, 100],
+            samples: 400,
+        };
+        assert_eq!(0.5, histogram.max_deviation());
+        let chi_square = histogram.chi_square();
+        assert_eq!(50.0, chi_square.statistic);
+        assert!(!chi_square.is_uniform(0.001));
+    }
+
+    #[test]
+    fn reports_collisions() {
+        let report = collisions(&CuidConstructor::new(), 10_001, 3).unwrap();
+        assert_eq!(10_001, report.samples);
+        assert!(report.is_collision_free());
+
+        // There are only 26 * 36 possible CUIDs of length 2
+        let report = collisions(&CuidConstructor::new().with_length(2), 1_000, 2).unwrap();
+        assert!(!report.is_collision_free());
+        assert!(report.duplicates.iter().all(|id| id.len() == 2));
+    }
+
+    #[test]
+    fn reports_errors() {
+        let constructor = CuidConstructor::new();
+        assert!(matches!(
+            histogram(&constructor, 10, 1),
+            Err(Error::TooFewBuckets { buckets: 1 })
+        ));
+        assert!(matches!(
+            collisions(&constructor, 10, 0),
+            Err(Error::NoThreads)
+        ));
+
+        struct Broken;
+
+        impl crate::Clock for Broken {
+            fn now_millis(&self) -> Result<u64, crate::ClockError> {
+                Err(crate::ClockError::new("no clock"))
+            }
+        }
+
+        let broken = constructor.with_clock(Broken);
+        assert!(matches!(
+            histogram(&broken, 10, 2),
+            Err(Error::Create(crate::Error::Clock(_)))
+        ));
+        assert!(matches!(
+            collisions(&broken, 10, 2),
+            Err(Error::Create(crate::Error::Clock(_)))
+        ));
+    }
+}
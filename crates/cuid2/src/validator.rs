@@ -0,0 +1,393 @@
+//! Configurable CUID validation with detailed errors
+
+use core::{fmt, ops::RangeInclusive};
+
+use cuid_util::BASE_36_DIGITS;
+
+use crate::{CuidConstructor, STARTING_CHARS};
+
+/// Validates CUIDs against an exact length or range of lengths, an alphabet,
+/// and a set of first characters, reporting why an invalid input failed.
+///
+/// By default, a CUID starts with a lowercase ASCII letter, followed by
+/// lowercase ASCII letters and digits. [`is_cuid2()`](crate::is_cuid2)
+/// accepts any length from [`MIN_LENGTH`](CuidConstructor::MIN_LENGTH) to
+/// [`MAX_LENGTH`](CuidConstructor::MAX_LENGTH), so a truncated CUID still
+/// passes it. A validator from [`CuidConstructor::validator()`] only accepts
+/// the length that constructor creates.
+///
+/// ```
+/// use cuid2::{CuidConstructor, ValidationError, Validator};
+///
+/// let constructor = CuidConstructor::new();
+/// let validator = constructor.validator();
+///
+/// assert!(validator.validate(&constructor.create_id()).is_ok());
+/// assert_eq!(
+///     Err(ValidationError::TooShort { length: 5, min: 24 }),
+///     validator.validate("tz4a9")
+/// );
+/// assert_eq!(
+///     Err(ValidationError::InvalidChar { index: 3, ch: 'A' }),
+///     Validator::new().validate("tz4A98xxat96")
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Validator {
+    lengths: RangeInclusive<u16>,
+    first_chars: CharSet,
+    alphabet: CharSet,
+}
+
+impl Validator {
+    /// Creates a validator that accepts the same CUIDs as
+    /// [`is_cuid2()`](crate::is_cuid2): from
+    /// [`MIN_LENGTH`](CuidConstructor::MIN_LENGTH) to
+    /// [`MAX_LENGTH`](CuidConstructor::MAX_LENGTH) characters, starting with
+    /// a lowercase ASCII letter and followed by lowercase ASCII letters and
+    /// digits.
+    pub const fn new() -> Self {
+        Self {
+            lengths: CuidConstructor::MIN_LENGTH..=CuidConstructor::MAX_LENGTH,
+            first_chars: CharSet::from_ascii(STARTING_CHARS.as_bytes()),
+            alphabet: CharSet::from_ascii(BASE_36_DIGITS),
+        }
+    }
+
+    /// Returns a validator that only accepts CUIDs of exactly `length`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length is less than
+    /// [`MIN_LENGTH`](CuidConstructor::MIN_LENGTH).
+    pub fn with_length(self, length: u16) -> Self {
+        self.with_length_range(length..=length)
+    }
+
+    /// Returns a validator that accepts CUIDs with any length in `lengths`.
+    ///
+    /// The lengths may exceed [`MAX_LENGTH`](CuidConstructor::MAX_LENGTH), to
+    /// validate longer CUIDs created by other implementations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is empty, or starts below
+    /// [`MIN_LENGTH`](CuidConstructor::MIN_LENGTH).
+    pub fn with_length_range(self, lengths: RangeInclusive<u16>) -> Self {
+        assert!(
+            !lengths.is_empty() && *lengths.start() >= CuidConstructor::MIN_LENGTH,
+            "Invalid CUID length range {lengths:?}: must not be empty or start below {}",
+            CuidConstructor::MIN_LENGTH
+        );
+        Self { lengths, ..self }
+    }
+
+    /// Returns a validator that only accepts CUIDs starting with one of the
+    /// characters in `first_chars`.
+    ///
+    /// ```
+    /// use cuid2::{ValidationError, Validator};
+    ///
+    /// let validator = Validator::new().with_first_chars("xyz");
+    ///
+    /// assert!(validator.is_valid("x1a2"));
+    /// assert_eq!(
+    ///     Err(ValidationError::InvalidFirstChar { ch: 'a' }),
+    ///     validator.validate("a1x2")
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `first_chars` is empty or contains anything but ASCII
+    /// characters.
+    pub fn with_first_chars(self, first_chars: &str) -> Self {
+        Self {
+            first_chars: CharSet::from_ascii(first_chars.as_bytes()),
+            ..self
+        }
+    }
+
+    /// Returns a validator that only accepts CUIDs whose characters after the
+    /// first are all in `alphabet`.
+    ///
+    /// ```
+    /// use cuid2::{ValidationError, Validator};
+    ///
+    /// // Also accept CUIDs that were uppercased after the first character
+    /// let validator = Validator::new()
+    ///     .with_alphabet("0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ");
+    ///
+    /// assert!(validator.is_valid("tZ4A98XXAT96"));
+    /// assert_eq!(
+    ///     Err(ValidationError::InvalidChar { index: 2, ch: '_' }),
+    ///     validator.validate("tZ_A98XXAT96")
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alphabet` is empty or contains anything but ASCII
+    /// characters.
+    pub fn with_alphabet(self, alphabet: &str) -> Self {
+        Self {
+            alphabet: CharSet::from_ascii(alphabet.as_bytes()),
+            ..self
+        }
+    }
+
+    /// Returns the range of lengths this validator accepts.
+    #[inline]
+    pub fn lengths(&self) -> RangeInclusive<u16> {
+        self.lengths.clone()
+    }
+
+    /// Checks whether `to_check` is a valid CUID, returning the first problem
+    /// found if not.
+    ///
+    /// The length is checked first, then the first character, then the rest
+    /// of the characters in order.
+    pub fn validate(&self, to_check: &str) -> Result<(), ValidationError> {
+        // Count chars rather than bytes, so a multi-byte char is reported as
+        // an invalid char rather than inflating the length.
+        let length = to_check.chars().count();
+        let (min, max) = (*self.lengths.start(), *self.lengths.end());
+        if length < usize::from(min) {
+            return Err(ValidationError::TooShort { length, min });
+        }
+        if length > usize::from(max) {
+            return Err(ValidationError::TooLong { length, max });
+        }
+
+        let mut chars = to_check.char_indices();
+        // Panic safety: the minimum length is at least MIN_LENGTH, so there
+        // is a first char.
+        let (_, first) = chars.next().expect("length was checked");
+        if !self.first_chars.contains(first) {
+            return Err(ValidationError::InvalidFirstChar { ch: first });
+        }
+        match chars.find(|(_, ch)| !self.alphabet.contains(*ch)) {
+            Some((index, ch)) => Err(ValidationError::InvalidChar { index, ch }),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns whether `to_check` is a valid CUID.
+    #[inline]
+    pub fn is_valid(&self, to_check: &str) -> bool {
+        self.validate(to_check).is_ok()
+    }
+}
+
+impl Default for Validator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A set of ASCII characters, with one bit per character.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct CharSet(u128);
+
+impl CharSet {
+    /// Creates a set of the given ASCII characters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chars` is empty or contains a non-ASCII byte.
+    const fn from_ascii(chars: &[u8]) -> Self {
+        assert!(
+            !chars.is_empty(),
+            "Invalid CUID characters: must not be empty"
+        );
+        let mut set = 0;
+        let mut i = 0;
+        while i < chars.len() {
+            assert!(
+                chars[i].is_ascii(),
+                "Invalid CUID characters: must all be ASCII"
+            );
+            set |= 1 << chars[i];
+            i += 1;
+        }
+        Self(set)
+    }
+
+    fn contains(self, ch: char) -> bool {
+        ch.is_ascii() && self.0 & (1 << ch as u32) != 0
+    }
+}
+
+impl fmt::Debug for CharSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chars = (0..128_u8).map(char::from).filter(|ch| self.contains(*ch));
+        f.debug_set().entries(chars).finish()
+    }
+}
+
+impl CuidConstructor {
+    /// Returns a validator that only accepts CUIDs of the length this
+    /// constructor creates.
+    ///
+    /// See [`Validator`] for details.
+    pub fn validator(&self) -> Validator {
+        Validator::new().with_length(self.length)
+    }
+}
+
+/// The reason a string is not a valid CUID, returned by
+/// [`Validator::validate()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationError {
+    /// The string has fewer characters than the minimum length.
+    TooShort {
+        /// The number of characters in the string.
+        length: usize,
+        /// The minimum length.
+        min: u16,
+    },
+    /// The string has more characters than the maximum length.
+    TooLong {
+        /// The number of characters in the string.
+        length: usize,
+        /// The maximum length.
+        max: u16,
+    },
+    /// The first character is not one of the validator's first characters,
+    /// by default the lowercase ASCII letters.
+    InvalidFirstChar {
+        /// The first character.
+        ch: char,
+    },
+    /// A character after the first is not in the validator's alphabet, by
+    /// default the lowercase ASCII letters and digits.
+    InvalidChar {
+        /// The byte index of the character in the string.
+        index: usize,
+        /// The character.
+        ch: char,
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooShort { length, min } => write!(
+                f,
+                "Invalid CUID: {length} characters is shorter than the minimum of {min}"
+            ),
+            Self::TooLong { length, max } => write!(
+                f,
+                "Invalid CUID: {length} characters is longer than the maximum of {max}"
+            ),
+            Self::InvalidFirstChar { ch } => write!(
+                f,
+                "Invalid CUID: first character {ch:?} is not allowed at the start"
+            ),
+            Self::InvalidChar { index, ch } => write!(
+                f,
+                "Invalid CUID: character {ch:?} at index {index} is not in the alphabet"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ValidationError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::is_cuid2;
+
+    #[test]
+    fn matches_is_cuid2() {
+        let validator = Validator::new();
+        for input in [
+            "",
+            "a",
+            "a1",
+            "1a",
+            "tz4a98xxat96iws9zmbrgj3a",
+            "tz4a98xxat96iws9zmbrgj3a12345678",
+            "tz4a98xxat96iws9zmbrgj3a123456789",
+            &"a".repeat(76),
+            &"a".repeat(77),
+            "tz4a98-xat96iws9zmbrgj3a",
+            "tz4a98XXat96iws9zmbrgj3a",
+            "é1",
+            "aé",
+        ] {
+            assert_eq!(is_cuid2(input), validator.is_valid(input), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn reports_first_problem() {
+        let validator = Validator::new().with_length_range(4..=40);
+        let cases = [
+            ("abc", ValidationError::TooShort { length: 3, min: 4 }),
+            ("aé", ValidationError::TooShort { length: 2, min: 4 }),
+            (
+                &"a".repeat(41),
+                ValidationError::TooLong {
+                    length: 41,
+                    max: 40,
+                },
+            ),
+            ("1bcd", ValidationError::InvalidFirstChar { ch: '1' }),
+            ("Ab_d", ValidationError::InvalidFirstChar { ch: 'A' }),
+            ("abé_", ValidationError::InvalidChar { index: 2, ch: 'é' }),
+            ("abcd_", ValidationError::InvalidChar { index: 4, ch: '_' }),
+        ];
+        for (input, err) in cases {
+            assert_eq!(Err(err), validator.validate(input), "{input:?}");
+        }
+        assert!(validator.is_valid(&"a".repeat(40)));
+    }
+
+    #[test]
+    fn custom_first_chars_and_alphabet() {
+        let validator = Validator::new()
+            .with_length(4)
+            .with_first_chars("ab")
+            .with_alphabet("01");
+        assert!(validator.is_valid("a010"));
+        assert!(validator.is_valid("b111"));
+        assert_eq!(
+            Err(ValidationError::InvalidFirstChar { ch: 'c' }),
+            validator.validate("c010")
+        );
+        assert_eq!(
+            Err(ValidationError::InvalidChar { index: 2, ch: '2' }),
+            validator.validate("a020")
+        );
+        assert_eq!(
+            Err(ValidationError::InvalidChar { index: 1, ch: 'é' }),
+            validator.validate("aé00")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "must all be ASCII")]
+    fn rejects_non_ascii_alphabet() {
+        let _ = Validator::new().with_alphabet("abcé");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn constructor_validator_uses_its_length() {
+        let constructor = CuidConstructor::new().with_length(10);
+        let validator = constructor.validator();
+        assert_eq!(10..=10, validator.lengths());
+        assert!(validator.is_valid(&constructor.create_id()));
+        assert!(!validator.is_valid(&CuidConstructor::new().create_id()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_short_length_range() {
+        let _ = Validator::new().with_length_range(1..=24);
+    }
+}
@@ -0,0 +1,93 @@
+//! Checks CUID construction against golden vectors from the reference
+//! implementation
+//!
+//! The vectors in `vectors/reference.json` were generated with
+//! `vectors/generate.js`, which documents how they were derived.
+
+use serde::Deserialize;
+
+/// A single set of inputs, with the reference implementation's outputs.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Vector {
+    timestamp: u64,
+    entropy: String,
+    counter: u64,
+    fingerprint: String,
+    first_letter: char,
+    length: u16,
+    /// The reference `hash()` of the inputs, which drops its first digit
+    hash: String,
+    /// The reference ID
+    id: String,
+    /// The ID expected from this crate, which keeps the digits the reference
+    /// drops
+    rust_id: String,
+}
+
+fn vectors() -> Vec<Vector> {
+    serde_json::from_str(include_str!("vectors/reference.json")).unwrap()
+}
+
+fn create_id(vector: &Vector) -> String {
+    cuid2::create_id_from_parts(
+        vector.timestamp,
+        &vector.entropy,
+        vector.counter,
+        &vector.fingerprint,
+        vector.first_letter,
+        vector.length,
+    )
+    .unwrap()
+}
+
+#[test]
+fn matches_golden_vectors() {
+    for vector in vectors() {
+        let id = create_id(&vector);
+        assert_eq!(vector.rust_id, id);
+        assert_eq!(usize::from(vector.length), id.len());
+        assert!(cuid2::is_cuid2(&id));
+    }
+}
+
+/// The reference implementation skips the first two digits of the hash: one
+/// in `hash()`, and one when taking `substring(1, length)`. This crate keeps
+/// them, so its IDs are the reference IDs shifted by two digits.
+#[test]
+fn keeps_digits_dropped_by_reference() {
+    for vector in vectors() {
+        let id = create_id(&vector);
+        let len = usize::from(vector.length);
+
+        assert_eq!(id[..1], vector.id[..1]);
+        // The reference's hash starts at the second digit of the whole hash
+        assert_eq!(vector.hash[..len - 2], id[2..]);
+        // and the reference's ID starts at the second digit of that
+        assert_eq!(vector.hash[1..len], vector.id[1..]);
+    }
+}
+
+#[test]
+fn rejects_invalid_parts() {
+    let vector = &vectors()[0];
+    let create = |first_letter, length| {
+        cuid2::create_id_from_parts(
+            vector.timestamp,
+            &vector.entropy,
+            vector.counter,
+            &vector.fingerprint,
+            first_letter,
+            length,
+        )
+    };
+
+    assert!(matches!(
+        create('A', 24),
+        Err(cuid2::Error::InvalidFirstLetter { letter: 'A' })
+    ));
+    assert!(matches!(
+        create('a', 1),
+        Err(cuid2::Error::InvalidLength { length: 1 })
+    ));
+}
@@ -0,0 +1,94 @@
+//! Checks that the non-allocating CUID constructors really don't allocate
+
+#![cfg(feature = "std")]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    fmt::{self, Write},
+};
+
+use cuid2::CuidConstructor;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Counts allocations made on the current thread.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // try_with, since the thread local may be torn down during thread exit
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations_during(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+/// A writer with a fixed capacity, which never allocates.
+struct FixedWriter {
+    buf: [u8; 32],
+    len: usize,
+}
+
+impl Write for FixedWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        self.buf
+            .get_mut(self.len..end)
+            .ok_or(fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+#[test]
+fn no_allocations() {
+    let constructor = CuidConstructor::new();
+
+    // The first ID on a thread initializes its thread-local state, which may
+    // allocate.
+    constructor.create_id();
+
+    let mut buf = [0; 24];
+    assert_eq!(
+        0,
+        allocations_during(|| {
+            constructor.create_id_into(&mut buf);
+        })
+    );
+
+    let mut writer = FixedWriter {
+        buf: [0; 32],
+        len: 0,
+    };
+    assert_eq!(
+        0,
+        allocations_during(|| constructor.write_id(&mut writer).unwrap())
+    );
+    assert_eq!(24, writer.len);
+
+    assert_eq!(
+        0,
+        allocations_during(|| {
+            constructor.create_inline_id();
+        })
+    );
+
+    // Sanity check that allocations are counted
+    assert_eq!(1, allocations_during(|| drop(constructor.create_id())));
+}
@@ -0,0 +1,110 @@
+// Generates reference.json, the golden vectors checked by tests/conformance.rs.
+//
+// The hashing and ID construction below are transcribed from the reference
+// implementation's src/index.js as of
+// https://github.com/paralleldrive/cuid2/blob/b5665387fdf7f947e135f030a545df22c5010a7d/src/index.js
+// with its inputs (time, salt, count, fingerprint, and first letter) passed in
+// rather than drawn from the clock, counter, and random source. Node's
+// built-in SHA3-512 stands in for `@noble/hashes/sha3`, so that no packages
+// need to be installed. Both hash the UTF-8 encoding of the input string.
+//
+// Usage: node generate.js > reference.json
+
+const { createHash } = require("crypto");
+
+const sha3 = (input) => createHash("sha3-512").update(input, "utf8").digest();
+
+function bufToBigInt(buf) {
+  let bits = 8n;
+
+  let value = 0n;
+  for (const i of buf.values()) {
+    const bi = BigInt(i);
+    value = (value << bits) + bi;
+  }
+  return value;
+}
+
+const hash = (input = "") => {
+  // Drop the first character because it will bias the histogram
+  // to the left.
+  return bufToBigInt(sha3(input)).toString(36).slice(1);
+};
+
+const cuid2 = ({ timestamp, entropy, counter, fingerprint, firstLetter, length }) => {
+  const time = timestamp.toString(36);
+  const count = counter.toString(36);
+  const hashInput = `${time + entropy + count + fingerprint}`;
+
+  return `${firstLetter + hash(hashInput).substring(1, length)}`;
+};
+
+const cases = [
+  {
+    timestamp: 1700000000000,
+    entropy: "k3j5n8q0w2e4r6t8y0u2i4o6",
+    counter: 12345,
+    fingerprint: "gqz1cxk6bn4h1k5ezxlw2xlt9k9m4d0n",
+    firstLetter: "c",
+    length: 24,
+  },
+  {
+    timestamp: 0,
+    entropy: "",
+    counter: 0,
+    fingerprint: "",
+    firstLetter: "a",
+    length: 24,
+  },
+  {
+    timestamp: 1700000000000,
+    entropy: "zz",
+    counter: 476782366,
+    fingerprint: "tenant-1",
+    firstLetter: "z",
+    length: 2,
+  },
+  {
+    timestamp: 1691474400000,
+    entropy: "0123456789abcdefghijklmnopqrstuv",
+    counter: 9007199254740991,
+    fingerprint: "yvx7tnj8bbqpcdfwqn0xwjt3ir1rm3y7",
+    firstLetter: "m",
+    length: 32,
+  },
+  {
+    timestamp: 4102444800000,
+    entropy: "a1b2c3d4e5",
+    counter: 36,
+    fingerprint: "host.example.com",
+    firstLetter: "q",
+    length: 10,
+  },
+  {
+    timestamp: 1234567890123,
+    entropy: "entropy with ünïcödé",
+    counter: 1,
+    fingerprint: "指纹",
+    firstLetter: "x",
+    length: 16,
+  },
+];
+
+// The reference drops two leading digits of the hash: one in hash(), and one
+// with substring(1, length). The Rust implementation deliberately keeps them,
+// since they do not bias the histogram, so its IDs are the first letter
+// followed by the leading digits of the whole hash.
+const rustCuid2 = ({ timestamp, entropy, counter, fingerprint, firstLetter, length }) => {
+  const hashInput = `${timestamp.toString(36) + entropy + counter.toString(36) + fingerprint}`;
+
+  return `${firstLetter + bufToBigInt(sha3(hashInput)).toString(36).substring(0, length - 1)}`;
+};
+
+const vectors = cases.map((inputs) => ({
+  ...inputs,
+  hash: hash(`${inputs.timestamp.toString(36)}${inputs.entropy}${inputs.counter.toString(36)}${inputs.fingerprint}`),
+  id: cuid2(inputs),
+  rustId: rustCuid2(inputs),
+}));
+
+console.log(JSON.stringify(vectors, null, 2));